@@ -3,7 +3,8 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use safe_deposit::execute_messages::msg::ExecuteMsg; //, QueryMsg};
+use safe_deposit::execute_messages::msg::{ExecuteMsg, ReceiveMsg}; //, QueryMsg};
+use safe_deposit::hooks::SafeDepositHookMsg;
 use safe_deposit::instantiation::msg::InstantiateMsg;
 use safe_deposit::query::query_message::QueryMsg;
 
@@ -17,6 +18,8 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(ReceiveMsg), &out_dir);
+    export_schema(&schema_for!(SafeDepositHookMsg), &out_dir);
 
     //export_schema(&schema_for!(State), &out_dir);
     //export_schema(&schema_for!(CountResponse), &out_dir);
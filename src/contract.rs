@@ -1,16 +1,26 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+};
 
 use crate::execute::admin::dispatch_admin;
 use crate::execute::default::dispatch_default;
 
 use crate::execute_messages::msg::{ExecuteMsg, MigrateMsg};
+use crate::hooks::{handle_hook_reply, HOOK_REPLY_ID};
 
 use crate::instantiation;
 use crate::instantiation::msg::InstantiateMsg;
-use crate::query::query_execute::{get_all_currency_accounts, get_currency_account};
+use crate::query::query_execute::{
+    get_admin, get_all_currency_accounts, get_all_locked_balances, get_config, get_currency,
+    get_currency_account, get_fees, get_handler, get_lock, get_locked_balances, get_pause_status,
+    get_solvency, get_totals, list_accounts_for_currency, list_currencies, list_handler_allowances,
+    list_handlers, list_locks_by_handler, list_locks_by_owner,
+};
 use crate::query::query_message::QueryMsg;
+use crate::state::state_writes;
+use crate::structs::Currency;
 
 use crate::error::ContractError;
 
@@ -34,8 +44,18 @@ pub fn instantiate(
 }
 
 #[entry_point]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    // No state migrations performed, just returned a Response
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let legacy_currencies = msg
+        .legacy_currencies
+        .unwrap_or_default()
+        .into_iter()
+        .map(|currency| currency.validate(deps.api))
+        .collect::<StdResult<Vec<Currency>>>()?;
+    state_writes::migrate_legacy_currencies(deps.storage, legacy_currencies)?;
+    state_writes::release_untracked_locks(deps.storage)?;
+    state_writes::rebuild_balance_indexes(deps.storage)?;
+    state_writes::admin::migrate_authorized_handlers(deps.storage)?;
+
     Ok(Response::default())
 }
 
@@ -55,14 +75,92 @@ pub fn execute(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        HOOK_REPLY_ID => handle_hook_reply(msg),
+        _ => Err(ContractError::Never {}),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
+        QueryMsg::Admin {} => to_binary(&get_admin(deps)?),
+        QueryMsg::Config {} => to_binary(&get_config(deps)?),
+        QueryMsg::PauseStatus {} => to_binary(&get_pause_status(deps)?),
         QueryMsg::GetBalance {
             account_owner,
             currency_id,
-        } => to_binary(&get_currency_account(deps, account_owner, currency_id)),
-        QueryMsg::GetAllBalances { account_owner } => {
-            to_binary(&get_all_currency_accounts(deps, account_owner))
+        } => to_binary(&get_currency_account(deps, account_owner, currency_id)?),
+        QueryMsg::GetAllBalances {
+            account_owner,
+            start_after,
+            limit,
+            hide_zero,
+        } => to_binary(&get_all_currency_accounts(
+            deps,
+            account_owner,
+            start_after,
+            limit,
+            hide_zero.unwrap_or(false),
+        )?),
+        QueryMsg::ListAccountsForCurrency {
+            currency,
+            start_after,
+            limit,
+        } => to_binary(&list_accounts_for_currency(
+            deps,
+            currency,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetCurrency { currency_id } => to_binary(&get_currency(deps, currency_id)?),
+        QueryMsg::ListCurrencies { start_after, limit } => {
+            to_binary(&list_currencies(deps, start_after, limit)?)
+        }
+        QueryMsg::GetLockedBalances {
+            account_owner,
+            currency_id,
+        } => to_binary(&get_locked_balances(deps, account_owner, currency_id)?),
+        QueryMsg::GetAllLockedBalances { account_owner } => {
+            to_binary(&get_all_locked_balances(deps, account_owner)?)
+        }
+        QueryMsg::ListHandlerAllowances {
+            account_owner,
+            start_after,
+            limit,
+        } => to_binary(&list_handler_allowances(
+            deps,
+            account_owner,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetLock { lock_id } => to_binary(&get_lock(deps, lock_id)?),
+        QueryMsg::ListLocksByOwner {
+            account_owner,
+            start_after,
+            limit,
+        } => to_binary(&list_locks_by_owner(
+            deps,
+            account_owner,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ListLocksByHandler {
+            handler,
+            start_after,
+            limit,
+        } => to_binary(&list_locks_by_handler(deps, handler, start_after, limit)?),
+        QueryMsg::Totals { start_after, limit } => {
+            to_binary(&get_totals(deps, start_after, limit)?)
+        }
+        QueryMsg::SolvencyCheck { currency_id } => {
+            to_binary(&get_solvency(deps, env, currency_id)?)
+        }
+        QueryMsg::Fees { currency_id } => to_binary(&get_fees(deps, currency_id)?),
+        QueryMsg::GetHandler { handler } => to_binary(&get_handler(deps, handler)?),
+        QueryMsg::ListHandlers { start_after, limit } => {
+            to_binary(&list_handlers(deps, start_after, limit)?)
         } //_ => return to_binary(&42),
     }
 }
@@ -2,29 +2,58 @@
 mod tests {
     use std::str::FromStr;
 
-    use cosmwasm_std::{coin, to_binary, Addr, Empty, Uint128};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{
+        coin, to_binary, Addr, BankMsg, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo,
+        Response, StdError, StdResult, Uint128, Uint256,
+    };
+    use cw_utils::{Duration, Expiration};
+    use serde::{Deserialize, Serialize};
 
     use cw20::Cw20Coin;
-    use cw_multi_test::{App, BankSudo, Contract, ContractWrapper, Executor};
+    use cw_multi_test::{App, AppResponse, BankSudo, Contract, ContractWrapper, Executor};
 
-    use crate::contract::{execute, instantiate, query};
-    use crate::execute_messages::msg::ExecuteMsg;
+    use crate::contract::{execute, instantiate, migrate, query, reply};
+    use crate::execute_messages::msg::{
+        Asset, DepositLock, DepositNotification, ExecuteMsg, MigrateMsg, ReceiveMsg, SettlementLeg,
+        Split, SplitShare, TransferItem, WithdrawItem,
+    };
     use crate::execute_messages::msg_admin::AdminExecuteMsg;
+    use crate::hooks::{SafeDepositHookMsg, HOOK_GAS_LIMIT};
     use crate::instantiation::msg::InstantiateMsg;
+    use crate::query::query_message::QueryMsg;
+    use crate::query::query_response::{
+        AdminResponse, AllCurrencyAccountResponse, ConfigResponse, CurrenciesResponse,
+        CurrencyAccountResponse, CurrencyBalance, CurrencyHoldersResponse, CurrencyResponse,
+        FeesResponse, HandlerAllowanceInfo, HandlerAllowancesResponse, HandlerLockedBalance,
+        HandlerResponse, HandlersResponse, HolderBalance, LockResponse, LockedBalancesResponse,
+        LocksResponse, PauseStatusResponse, SolvencyResponse, TotalsResponse,
+    };
+    use crate::state::state_entries::{
+        BALANCES, HANDLER_LOCKS, LEGACY_VALID_CURRENCIES, VALID_CURRENCIES,
+    };
+    use crate::state::state_reads;
+    use crate::structs::{
+        AssetInfo, Currency, CurrencyAccount, CurrencyStatus, Fee, FeeSchedule, HandlerCap,
+        HandlerOperation, HandlerRecord, PauseStatus,
+    };
+    use crate::ContractError;
 
     const TEST_DENOM_NATIVE: &str = "test_native";
     const TEST_DENOM_CW20: &str = "test_cw20";
     const TEST_CREATOR: &str = "creator";
     const ALLOWED_HANDLER: &str = "handler";
+    const ALLOWED_HANDLER2: &str = "handler2";
     const TEST_USER: &str = "user";
-    const _TEST_USER2: &str = "user2";
+    const TEST_USER2: &str = "user2";
 
     const _TEST_PRICE: u64 = 10000000;
 
     const _TEST_INVALID_DENOM: &str = "notuusd";
 
     pub fn contract_vault() -> Box<dyn Contract<Empty>> {
-        let contract = ContractWrapper::new_with_empty(execute, instantiate, query); //.with_reply(reply);
+        let contract =
+            ContractWrapper::new_with_empty(execute, instantiate, query).with_reply(reply);
         Box::new(contract)
     }
 
@@ -37,6 +66,79 @@ mod tests {
         Box::new(contract)
     }
 
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum MockReceiverMsg {
+        SafeDepositHook(SafeDepositHookMsg),
+    }
+
+    // accepts every hook, except deposits with a "fail" payload
+    fn mock_receiver_execute(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: MockReceiverMsg,
+    ) -> StdResult<Response> {
+        match msg {
+            MockReceiverMsg::SafeDepositHook(SafeDepositHookMsg::DepositReceived {
+                payload,
+                ..
+            }) => {
+                if payload == Some(Binary::from(b"fail")) {
+                    return Err(StdError::generic_err("hook rejected"));
+                }
+                return Ok(Response::new().add_attribute("hook", "deposit_received"));
+            }
+            MockReceiverMsg::SafeDepositHook(SafeDepositHookMsg::DepositLocked { .. }) => {
+                return Ok(Response::new().add_attribute("hook", "deposit_locked"));
+            }
+            MockReceiverMsg::SafeDepositHook(SafeDepositHookMsg::LockReleased { .. }) => {
+                return Ok(Response::new().add_attribute("hook", "lock_released"));
+            }
+        }
+    }
+
+    fn mock_receiver_instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        return Ok(Response::new());
+    }
+
+    fn mock_receiver_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        return Err(StdError::generic_err("no queries"));
+    }
+
+    // rejects every hook, as the chain does when a receiver exhausts its gas limit
+    fn mock_broken_receiver_execute(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: MockReceiverMsg,
+    ) -> StdResult<Response> {
+        return Err(StdError::generic_err("out of gas"));
+    }
+
+    pub fn contract_mock_broken_receiver() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new_with_empty(
+            mock_broken_receiver_execute,
+            mock_receiver_instantiate,
+            mock_receiver_query,
+        );
+        Box::new(contract)
+    }
+
+    pub fn contract_mock_receiver() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new_with_empty(
+            mock_receiver_execute,
+            mock_receiver_instantiate,
+            mock_receiver_query,
+        );
+        Box::new(contract)
+    }
+
     pub fn setup_env() -> (App, Addr) {
         let owner = Addr::unchecked(TEST_CREATOR);
 
@@ -52,7 +154,11 @@ mod tests {
 
         //let _cw20_contract_id = router.store_code(contract_cw20());
 
-        let msg = InstantiateMsg {};
+        let msg = InstantiateMsg {
+            admin: None,
+            currencies: vec![],
+            handlers: vec![],
+        };
 
         let mocked_contract_addr = router
             .instantiate_contract(
@@ -105,6 +211,64 @@ mod tests {
         return mocked_contract_addr;
     }
 
+    pub fn native_currency() -> Currency {
+        return Currency::Native {
+            name: "Test Native".into(),
+            denom: TEST_DENOM_NATIVE.into(),
+            ticker: "TNAT".into(),
+        };
+    }
+
+    pub fn cw20_currency(token_address: &Addr) -> Currency {
+        return Currency::Cw20 {
+            name: TEST_DENOM_CW20.into(),
+            ticker: "cwtest".into(),
+            token_address: token_address.to_string(),
+        };
+    }
+
+    pub fn approve_handler(
+        router: &mut App,
+        contract_address: &Addr,
+        owner: &Addr,
+        handler: &str,
+        currency_id: &str,
+        max_amount: &str,
+    ) {
+        let msg = ExecuteMsg::ApproveHandler {
+            handler: handler.into(),
+            currency_identifier: currency_id.into(),
+            max_amount: max_amount.into(),
+            expires: None,
+        };
+
+        router
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    pub fn wasm_attribute(res: &AppResponse, key: &str) -> String {
+        return res
+            .events
+            .iter()
+            .filter(|event| event.ty == "wasm")
+            .flat_map(|event| event.attributes.iter())
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| attribute.value.clone())
+            .unwrap();
+    }
+
+    pub fn balance_change_attribute(res: &AppResponse, key: &str) -> String {
+        return res
+            .events
+            .iter()
+            .filter(|event| event.ty == "wasm-balance_change")
+            .flat_map(|event| event.attributes.iter())
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| attribute.value.clone())
+            .unwrap();
+    }
+
     #[test]
     fn instantiate_success() {
         let (_, _) = setup_env();
@@ -134,7 +298,7 @@ mod tests {
         let owner = Addr::unchecked(TEST_CREATOR);
 
         let admin_msg = AdminExecuteMsg::AddValidCurrency {
-            currency_id: TEST_DENOM_NATIVE.into(),
+            currency: native_currency(),
         };
         let msg = ExecuteMsg::Admin(admin_msg);
 
@@ -152,7 +316,7 @@ mod tests {
         let owner = Addr::unchecked(TEST_CREATOR);
 
         let admin_msg = AdminExecuteMsg::AddValidCurrency {
-            currency_id: TEST_DENOM_NATIVE.to_string(),
+            currency: native_currency(),
         };
         let msg = ExecuteMsg::Admin(admin_msg);
 
@@ -162,6 +326,8 @@ mod tests {
 
         let msg = ExecuteMsg::DepositNative {
             beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
         };
         let _res = app
             .execute_contract(
@@ -172,7 +338,22 @@ mod tests {
             )
             .unwrap();
 
-        //let msg = AdminExecuteMsg::
+        // the depositor is recorded when depositing for another address
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: TEST_USER.into(),
+            notify: None,
+            lock: None,
+        };
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(1000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+        assert_eq!(balance_change_attribute(&res, "owner"), TEST_USER);
+        assert_eq!(balance_change_attribute(&res, "depositor"), TEST_CREATOR);
     }
 
     #[test]
@@ -182,7 +363,7 @@ mod tests {
         let owner = Addr::unchecked(TEST_CREATOR);
 
         let admin_msg = AdminExecuteMsg::AddValidCurrency {
-            currency_id: TEST_DENOM_NATIVE.to_string(),
+            currency: native_currency(),
         };
         let msg = ExecuteMsg::Admin(admin_msg);
 
@@ -192,6 +373,8 @@ mod tests {
 
         let msg = ExecuteMsg::DepositNative {
             beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
         };
         let _res = app
             .execute_contract(
@@ -217,18 +400,12 @@ mod tests {
     fn deposit_cw20_currency_receive_hook() {
         let (mut app, contract_address) = setup_env();
 
-        let cw_address = create_cw20(
-            &mut app,
-            TEST_DENOM_CW20,
-            "cwtest",
-            TEST_CREATOR.clone(),
-            5000000,
-        );
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
 
         let owner = Addr::unchecked(TEST_CREATOR);
 
         let admin_msg = AdminExecuteMsg::AddValidCurrency {
-            currency_id: cw_address.to_string(),
+            currency: cw20_currency(&cw_address),
         };
         let msg = ExecuteMsg::Admin(admin_msg);
 
@@ -237,36 +414,34 @@ mod tests {
             .unwrap();
 
         // encode beneficiary
-        let beneficiary_data = to_binary(&owner.clone().to_string()).unwrap();
+        let beneficiary_data = to_binary(&ReceiveMsg::DepositFor {
+            beneficiary: TEST_USER.into(),
+            notify: None,
+        })
+        .unwrap();
         let msg = cw20_base::msg::ExecuteMsg::Send {
             contract: contract_address.clone().into_string(),
             amount: Uint128::from_str("500000").unwrap(),
             msg: beneficiary_data,
         };
 
-        let _res = app
+        let res = app
             .execute_contract(owner.clone(), cw_address.clone(), &msg, &[])
             .unwrap();
-
-        //let msg = AdminExecuteMsg::
+        assert_eq!(balance_change_attribute(&res, "owner"), TEST_USER);
+        assert_eq!(balance_change_attribute(&res, "depositor"), TEST_CREATOR);
     }
 
     #[test]
     fn deposit_cw20_currency_allowances() {
         let (mut app, contract_address) = setup_env();
 
-        let cw_address = create_cw20(
-            &mut app,
-            TEST_DENOM_CW20,
-            "cwtest",
-            TEST_CREATOR.clone(),
-            5000000,
-        );
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
 
         let owner = Addr::unchecked(TEST_CREATOR);
 
         let admin_msg = AdminExecuteMsg::AddValidCurrency {
-            currency_id: cw_address.to_string(),
+            currency: cw20_currency(&cw_address),
         };
         let msg = ExecuteMsg::Admin(admin_msg);
 
@@ -284,35 +459,80 @@ mod tests {
             .execute_contract(owner.clone(), cw_address.clone(), &msg, &[])
             .unwrap();
 
+        // the allowance of another owner cannot be pulled, with or without a lock
+        for lock in [
+            None,
+            Some(DepositLock {
+                handler: ALLOWED_HANDLER.into(),
+                amount: "50000".into(),
+                expires: None,
+            }),
+        ] {
+            let msg = ExecuteMsg::DepositCw20 {
+                sender: owner.clone().into(),
+                beneficiary: TEST_USER2.into(),
+                token_address: cw_address.clone().into_string(),
+                amount: "50000".to_string(),
+                notify: None,
+                lock: lock,
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(TEST_USER2),
+                    contract_address.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized {}
+            );
+        }
+
         let msg = ExecuteMsg::DepositCw20 {
             sender: owner.clone().into(),
-            beneficiary: owner.clone().into(),
+            beneficiary: "x".into(),
+            token_address: cw_address.clone().into_string(),
+            amount: "50000".to_string(),
+            notify: None,
+            lock: None,
+        };
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InvalidDepositBeneficiary {
+                beneficiary: "x".into(),
+            }
+        );
+
+        let msg = ExecuteMsg::DepositCw20 {
+            sender: owner.clone().into(),
+            beneficiary: TEST_USER.into(),
             token_address: cw_address.into_string(),
             amount: "50000".to_string(),
+            notify: None,
+            lock: None,
         };
-        let _res = app
+        let res = app
             .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
             .unwrap();
-
-        //let msg = AdminExecuteMsg::
+        assert_eq!(balance_change_attribute(&res, "owner"), TEST_USER);
+        assert_eq!(balance_change_attribute(&res, "depositor"), TEST_CREATOR);
     }
 
     #[test]
     fn withdraw_cw20_currency_after_allowance_deposit() {
         let (mut app, contract_address) = setup_env();
 
-        let cw_address = create_cw20(
-            &mut app,
-            TEST_DENOM_CW20,
-            "cwtest",
-            TEST_CREATOR.clone(),
-            5000000,
-        );
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
 
         let owner = Addr::unchecked(TEST_CREATOR);
 
         let admin_msg = AdminExecuteMsg::AddValidCurrency {
-            currency_id: cw_address.to_string(),
+            currency: cw20_currency(&cw_address),
         };
         let msg = ExecuteMsg::Admin(admin_msg);
 
@@ -335,6 +555,8 @@ mod tests {
             beneficiary: owner.clone().into(),
             token_address: cw_address.clone().into_string(),
             amount: "50000".to_string(),
+            notify: None,
+            lock: None,
         };
         let _res = app
             .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
@@ -355,18 +577,12 @@ mod tests {
     fn withdraw_cw20_currency_after_receive_hook_deposit() {
         let (mut app, contract_address) = setup_env();
 
-        let cw_address = create_cw20(
-            &mut app,
-            TEST_DENOM_CW20,
-            "cwtest",
-            TEST_CREATOR.clone(),
-            5000000,
-        );
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
 
         let owner = Addr::unchecked(TEST_CREATOR);
 
         let admin_msg = AdminExecuteMsg::AddValidCurrency {
-            currency_id: cw_address.to_string(),
+            currency: cw20_currency(&cw_address),
         };
         let msg = ExecuteMsg::Admin(admin_msg);
 
@@ -375,7 +591,11 @@ mod tests {
             .unwrap();
 
         // encode beneficiary
-        let beneficiary_data = to_binary(&owner.clone().to_string()).unwrap();
+        let beneficiary_data = to_binary(&ReceiveMsg::DepositFor {
+            beneficiary: owner.clone().into_string(),
+            notify: None,
+        })
+        .unwrap();
         let msg = cw20_base::msg::ExecuteMsg::Send {
             contract: contract_address.clone().into_string(),
             amount: Uint128::from_str("500000").unwrap(),
@@ -403,7 +623,7 @@ mod tests {
         let owner = Addr::unchecked(TEST_CREATOR);
 
         let admin_msg = AdminExecuteMsg::AddValidCurrency {
-            currency_id: TEST_DENOM_NATIVE.to_string(),
+            currency: native_currency(),
         };
         let msg = ExecuteMsg::Admin(admin_msg);
 
@@ -413,6 +633,8 @@ mod tests {
 
         let msg = ExecuteMsg::DepositNative {
             beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
         };
         let _res = app
             .execute_contract(
@@ -433,25 +655,3732 @@ mod tests {
             .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
             .unwrap();
 
+        approve_handler(
+            &mut app,
+            &contract_address,
+            &owner,
+            ALLOWED_HANDLER,
+            TEST_DENOM_NATIVE,
+            "256000",
+        );
+
         let handler = Addr::unchecked(ALLOWED_HANDLER);
         let msg = ExecuteMsg::Lock {
             account: owner.clone().into(),
             currency_identifier: TEST_DENOM_NATIVE.into(),
             amount: "56000".into(),
+            expires: None,
         };
         let _res = app
             .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
             .unwrap();
 
+        // zero amounts are rejected by every locked value operation
+        for msg in [
+            ExecuteMsg::Lock {
+                account: owner.clone().into(),
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: "0".into(),
+                expires: None,
+            },
+            ExecuteMsg::TransferLocked {
+                account: owner.clone().into(),
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: "0".into(),
+                beneficiary: TEST_USER.into(),
+                lock_id: None,
+                splits: None,
+            },
+            ExecuteMsg::Unlock {
+                account: owner.clone().into(),
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: "0".into(),
+                lock_id: None,
+            },
+        ] {
+            let err = app
+                .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::InvalidZeroAmount {}
+            );
+        }
+
         let msg = ExecuteMsg::TransferLocked {
             account: owner.clone().into(),
             currency_identifier: TEST_DENOM_NATIVE.into(),
             amount: "56000".into(),
             beneficiary: TEST_USER.into(),
+            lock_id: None,
+            splits: None,
         };
         let _res = app
             .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
             .unwrap();
         //let msg = AdminExecuteMsg::
     }
+
+    #[test]
+    fn locks_are_scoped_per_handler() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+
+        let admin_msg = AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        };
+        let msg = ExecuteMsg::Admin(admin_msg);
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(256000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        for handler in [ALLOWED_HANDLER, ALLOWED_HANDLER2] {
+            let admin_msg = AdminExecuteMsg::SetAuthorizationStatus {
+                target: handler.into(),
+                new_status: true,
+            };
+            let msg = ExecuteMsg::Admin(admin_msg);
+
+            let _res = app
+                .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        approve_handler(
+            &mut app,
+            &contract_address,
+            &owner,
+            ALLOWED_HANDLER,
+            TEST_DENOM_NATIVE,
+            "256000",
+        );
+
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+        let msg = ExecuteMsg::Lock {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "56000".into(),
+            expires: None,
+        };
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        // second handler cannot release or transfer funds locked by the first one
+        approve_handler(
+            &mut app,
+            &contract_address,
+            &owner,
+            ALLOWED_HANDLER2,
+            TEST_DENOM_NATIVE,
+            "256000",
+        );
+
+        let handler2 = Addr::unchecked(ALLOWED_HANDLER2);
+        let msg = ExecuteMsg::Unlock {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+            lock_id: None,
+        };
+        let err = app
+            .execute_contract(handler2.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientFundsLockedForUnlock {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                available: "0".into(),
+                required: "1000".into(),
+            }
+        );
+
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+            beneficiary: ALLOWED_HANDLER2.into(),
+            lock_id: None,
+            splits: None,
+        };
+        let _err = app
+            .execute_contract(handler2.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+
+        let msg = ExecuteMsg::Lock {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "4000".into(),
+            expires: None,
+        };
+        let _res = app
+            .execute_contract(handler2.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: LockedBalancesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetLockedBalances {
+                    account_owner: owner.clone().into(),
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res.locks,
+            vec![
+                HandlerLockedBalance {
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                    handler: ALLOWED_HANDLER.into(),
+                    locked: 56000,
+                },
+                HandlerLockedBalance {
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                    handler: ALLOWED_HANDLER2.into(),
+                    locked: 4000,
+                },
+            ]
+        );
+
+        // releasing the whole lock removes it from the breakdown
+        let msg = ExecuteMsg::Unlock {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "56000".into(),
+            lock_id: None,
+        };
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: LockedBalancesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetAllLockedBalances {
+                    account_owner: owner.clone().into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res.locks,
+            vec![HandlerLockedBalance {
+                currency_id: TEST_DENOM_NATIVE.into(),
+                handler: ALLOWED_HANDLER2.into(),
+                locked: 4000,
+            }]
+        );
+    }
+
+    #[test]
+    fn lock_ids_are_tracked_and_partially_consumed() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+
+        let admin_msg = AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        };
+        let msg = ExecuteMsg::Admin(admin_msg);
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(256000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let admin_msg = AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        };
+        let msg = ExecuteMsg::Admin(admin_msg);
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        approve_handler(
+            &mut app,
+            &contract_address,
+            &owner,
+            ALLOWED_HANDLER,
+            TEST_DENOM_NATIVE,
+            "256000",
+        );
+
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+        let mut lock_ids = vec![];
+        for amount in ["10000", "20000"] {
+            let msg = ExecuteMsg::Lock {
+                account: owner.clone().into(),
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: amount.into(),
+                expires: None,
+            };
+            let res = app
+                .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+                .unwrap();
+            lock_ids.push(u64::from_str(&wasm_attribute(&res, "lock_id")).unwrap());
+        }
+        assert_eq!(lock_ids, vec![1, 2]);
+
+        // cannot take more than the targeted lock holds
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "15000".into(),
+            beneficiary: TEST_USER.into(),
+            lock_id: Some(1),
+            splits: None,
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientFundsLockedForTransfer {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                available: "10000".into(),
+                required: "15000".into(),
+            }
+        );
+
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "5000".into(),
+            beneficiary: TEST_USER.into(),
+            lock_id: Some(2),
+            splits: None,
+        };
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: LockResponse = app
+            .wrap()
+            .query_wasm_smart(contract_address.clone(), &QueryMsg::GetLock { lock_id: 2 })
+            .unwrap();
+        assert_eq!(res.lock.amount, 15000);
+        assert_eq!(res.lock.initial_amount, 20000);
+        assert_eq!(res.lock.handler, handler);
+
+        // pooled unlock consumes the oldest lock first
+        let msg = ExecuteMsg::Unlock {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "12000".into(),
+            lock_id: None,
+        };
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: LocksResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::ListLocksByOwner {
+                    account_owner: owner.clone().into(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.locks.len(), 1);
+        assert_eq!(res.locks[0].lock_id, 2);
+        assert_eq!(res.locks[0].lock.amount, 13000);
+
+        let res: LocksResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::ListLocksByHandler {
+                    handler: ALLOWED_HANDLER.into(),
+                    start_after: Some(2),
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.locks, vec![]);
+
+        let _err = app
+            .wrap()
+            .query_wasm_smart::<LockResponse>(
+                contract_address.clone(),
+                &QueryMsg::GetLock { lock_id: 1 },
+            )
+            .unwrap_err();
+    }
+
+    #[test]
+    fn expired_lock_is_reclaimed_by_owner() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+
+        let admin_msg = AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        };
+        let msg = ExecuteMsg::Admin(admin_msg);
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(256000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let admin_msg = AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        };
+        let msg = ExecuteMsg::Admin(admin_msg);
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        approve_handler(
+            &mut app,
+            &contract_address,
+            &owner,
+            ALLOWED_HANDLER,
+            TEST_DENOM_NATIVE,
+            "256000",
+        );
+
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+        let expiration_height = app.block_info().height + 10;
+        let msg = ExecuteMsg::Lock {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "56000".into(),
+            expires: Some(Expiration::AtHeight(expiration_height)),
+        };
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        // lock is still active
+        let msg = ExecuteMsg::ReclaimExpiredLock { lock_id: 1 };
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::LockNotExpired { lock_id: 1 }
+        );
+
+        app.update_block(|block| block.height = expiration_height);
+
+        // handler can no longer use the lock
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "56000".into(),
+            beneficiary: ALLOWED_HANDLER.into(),
+            lock_id: Some(1),
+            splits: None,
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Expired {}
+        );
+
+        // only the owner can reclaim
+        let msg = ExecuteMsg::ReclaimExpiredLock { lock_id: 1 };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized {}
+        );
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: CurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetBalance {
+                    account_owner: owner.clone().into(),
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.account.available, 256000);
+        assert_eq!(res.account.locked, 0);
+
+        let res: LockedBalancesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetAllLockedBalances {
+                    account_owner: owner.clone().into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.locks, vec![]);
+    }
+
+    #[test]
+    fn lock_requires_owner_allowance() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+
+        let admin_msg = AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        };
+        let msg = ExecuteMsg::Admin(admin_msg);
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(256000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let admin_msg = AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        };
+        let msg = ExecuteMsg::Admin(admin_msg);
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+        let lock_msg = ExecuteMsg::Lock {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "30000".into(),
+            expires: None,
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &lock_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::NoAllowance {}
+        );
+
+        // cannot approve itself
+        let msg = ExecuteMsg::ApproveHandler {
+            handler: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            max_amount: "50000".into(),
+            expires: None,
+        };
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::CannotSetOwnAccount {}
+        );
+
+        let expiration_height = app.block_info().height + 10;
+        let msg = ExecuteMsg::ApproveHandler {
+            handler: ALLOWED_HANDLER.into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            max_amount: "50000".into(),
+            expires: Some(Expiration::AtHeight(expiration_height)),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &lock_msg, &[])
+            .unwrap();
+
+        // the allowance is spent by each lock, moving locked value away does not restore it
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "30000".into(),
+            beneficiary: ALLOWED_HANDLER.into(),
+            lock_id: None,
+            splits: None,
+        };
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &lock_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientAllowance {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                available: "20000".into(),
+                required: "30000".into(),
+            }
+        );
+
+        let res: HandlerAllowancesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::ListHandlerAllowances {
+                    account_owner: owner.clone().into(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res.allowances,
+            vec![HandlerAllowanceInfo {
+                currency_id: TEST_DENOM_NATIVE.into(),
+                handler: ALLOWED_HANDLER.into(),
+                remaining: 20000,
+                locked: 0,
+                expires: Expiration::AtHeight(expiration_height),
+            }]
+        );
+
+        app.update_block(|block| block.height = expiration_height);
+
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &lock_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Expired {}
+        );
+
+        let msg = ExecuteMsg::RevokeHandler {
+            handler: ALLOWED_HANDLER.into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: HandlerAllowancesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::ListHandlerAllowances {
+                    account_owner: owner.clone().into(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.allowances, vec![]);
+
+        // a used up allowance is removed
+        approve_handler(
+            &mut app,
+            &contract_address,
+            &owner,
+            ALLOWED_HANDLER,
+            TEST_DENOM_NATIVE,
+            "30000",
+        );
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &lock_msg, &[])
+            .unwrap();
+
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &lock_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::NoAllowance {}
+        );
+    }
+
+    #[test]
+    fn transfer_available_between_depositors() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+
+        let admin_msg = AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        };
+        let msg = ExecuteMsg::Admin(admin_msg);
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(256000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: TEST_USER.into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "300000".into(),
+        };
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientFundsAvailableForTransfer {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                available: "256000".into(),
+                required: "300000".into(),
+            }
+        );
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+        };
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::CannotSetOwnAccount {}
+        );
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: TEST_USER.into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "6000".into(),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::BatchTransfer {
+            transfers: vec![
+                TransferItem {
+                    recipient: TEST_USER.into(),
+                    currency_identifier: TEST_DENOM_NATIVE.into(),
+                    amount: "4000".into(),
+                },
+                TransferItem {
+                    recipient: TEST_USER2.into(),
+                    currency_identifier: TEST_DENOM_NATIVE.into(),
+                    amount: "50000".into(),
+                },
+            ],
+        };
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        for (account_owner, available) in [
+            (TEST_CREATOR, 196000),
+            (TEST_USER, 10000),
+            (TEST_USER2, 50000),
+        ] {
+            let res: CurrencyAccountResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_address.clone(),
+                    &QueryMsg::GetBalance {
+                        account_owner: account_owner.into(),
+                        currency_id: TEST_DENOM_NATIVE.into(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(res.account.available, available);
+        }
+    }
+
+    #[test]
+    fn currency_registry_checks_currency_kind() {
+        let (mut app, contract_address) = setup_env();
+
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+
+        for currency in [native_currency(), cw20_currency(&cw_address)] {
+            let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency { currency: currency });
+            let _res = app
+                .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        // identifier cannot be registered again with another kind
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: Currency::Native {
+                name: "Fake".into(),
+                denom: cw_address.to_string(),
+                ticker: "FAKE".into(),
+            },
+        });
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::CurrencyKindMismatch {
+                currency_identifier: cw_address.to_string(),
+            }
+        );
+
+        let beneficiary_data = to_binary(&ReceiveMsg::DepositFor {
+            beneficiary: owner.clone().into_string(),
+            notify: None,
+        })
+        .unwrap();
+        let msg = cw20_base::msg::ExecuteMsg::Send {
+            contract: contract_address.clone().into_string(),
+            amount: Uint128::from_str("500000").unwrap(),
+            msg: beneficiary_data,
+        };
+        let _res = app
+            .execute_contract(owner.clone(), cw_address.clone(), &msg, &[])
+            .unwrap();
+
+        // cw20 balance cannot leave through the native path
+        let msg = ExecuteMsg::WithdrawNative {
+            beneficiary: owner.clone().into_string(),
+            denom: cw_address.to_string(),
+            amount: "50000".into(),
+        };
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::NativeCurrencyNotAccepted {
+                denom: cw_address.to_string(),
+            }
+        );
+
+        let res: CurrencyResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetCurrency {
+                    currency_id: cw_address.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.currency, cw20_currency(&cw_address));
+        assert_eq!(res.status, CurrencyStatus::Active);
+
+        let res: CurrenciesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::ListCurrencies {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.currencies.len(), 2);
+        assert!(res
+            .currencies
+            .iter()
+            .any(|entry| entry.currency == native_currency()));
+    }
+
+    #[test]
+    fn delisted_currency_winds_down() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+
+        let admin_msg = AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        };
+        let msg = ExecuteMsg::Admin(admin_msg);
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let deposit_msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &deposit_msg,
+                &[coin(256000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let admin_msg = AdminExecuteMsg::SetCurrencyStatus {
+            currency_id: TEST_DENOM_NATIVE.into(),
+            status: CurrencyStatus::WithdrawOnly,
+        };
+        let msg = ExecuteMsg::Admin(admin_msg);
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &deposit_msg,
+                &[coin(1000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::CurrencyOperationDisabled {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                operation: "deposit".into(),
+            }
+        );
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: TEST_USER.into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+        };
+        let _err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+
+        // currency cannot be removed while users still hold it
+        let admin_msg = AdminExecuteMsg::RemoveValidCurrency {
+            currency_id: TEST_DENOM_NATIVE.into(),
+        };
+        let remove_msg = ExecuteMsg::Admin(admin_msg);
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &remove_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::CurrencyStillHeld {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+            }
+        );
+
+        // users can still exit
+        let msg = ExecuteMsg::WithdrawNative {
+            beneficiary: owner.clone().into_string(),
+            denom: TEST_DENOM_NATIVE.to_string(),
+            amount: "256000".into(),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &remove_msg, &[])
+            .unwrap();
+
+        let _err = app
+            .wrap()
+            .query_wasm_smart::<CurrencyResponse>(
+                contract_address.clone(),
+                &QueryMsg::GetCurrency {
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap_err();
+    }
+
+    #[test]
+    fn admin_handover_in_two_steps() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let new_admin = Addr::unchecked(TEST_USER);
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::ProposeNewAdmin {
+            new_admin: TEST_USER.into(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: AdminResponse = app
+            .wrap()
+            .query_wasm_smart(contract_address.clone(), &QueryMsg::Admin {})
+            .unwrap();
+        assert_eq!(
+            res,
+            AdminResponse {
+                admin: Some(TEST_CREATOR.into()),
+                pending_admin: Some(TEST_USER.into()),
+            }
+        );
+
+        // only the proposed admin can accept
+        let accept_msg = ExecuteMsg::Admin(AdminExecuteMsg::AcceptAdmin {});
+        let err = app
+            .execute_contract(
+                Addr::unchecked(TEST_USER2),
+                contract_address.clone(),
+                &accept_msg,
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized {}
+        );
+
+        let _res = app
+            .execute_contract(
+                new_admin.clone(),
+                contract_address.clone(),
+                &accept_msg,
+                &[],
+            )
+            .unwrap();
+
+        let res: AdminResponse = app
+            .wrap()
+            .query_wasm_smart(contract_address.clone(), &QueryMsg::Admin {})
+            .unwrap();
+        assert_eq!(
+            res,
+            AdminResponse {
+                admin: Some(TEST_USER.into()),
+                pending_admin: None,
+            }
+        );
+
+        // previous admin lost its rights
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        });
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized {}
+        );
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::ProposeNewAdmin {
+            new_admin: TEST_CREATOR.into(),
+        });
+        let _res = app
+            .execute_contract(new_admin.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::CancelAdminProposal {});
+        let _res = app
+            .execute_contract(new_admin.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &accept_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::NoPendingAdmin {}
+        );
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::RenounceAdmin {});
+        let _res = app
+            .execute_contract(new_admin.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: AdminResponse = app
+            .wrap()
+            .query_wasm_smart(contract_address.clone(), &QueryMsg::Admin {})
+            .unwrap();
+        assert_eq!(
+            res,
+            AdminResponse {
+                admin: None,
+                pending_admin: None,
+            }
+        );
+    }
+
+    #[test]
+    fn instantiate_with_admin_currencies_and_handlers() {
+        let mut app = App::default();
+        let owner = Addr::unchecked(TEST_CREATOR);
+
+        let vault_contract_id = app.store_code(contract_vault());
+
+        let msg = InstantiateMsg {
+            admin: Some(TEST_USER.into()),
+            currencies: vec![native_currency()],
+            handlers: vec![ALLOWED_HANDLER.into()],
+        };
+        let contract_address = app
+            .instantiate_contract(
+                vault_contract_id,
+                owner.clone(),
+                &msg,
+                &[],
+                "vault",
+                Some(owner.clone().into()),
+            )
+            .unwrap();
+
+        let res: AdminResponse = app
+            .wrap()
+            .query_wasm_smart(contract_address.clone(), &QueryMsg::Admin {})
+            .unwrap();
+        assert_eq!(res.admin, Some(TEST_USER.into()));
+
+        let res: CurrencyResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetCurrency {
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.currency, native_currency());
+
+        // handler is usable straight away
+        let deposit_msg = ExecuteMsg::DepositNative {
+            beneficiary: TEST_USER.into(),
+            notify: None,
+            lock: None,
+        };
+        app.sudo(cw_multi_test::SudoMsg::Bank(BankSudo::Mint {
+            to_address: TEST_USER.into(),
+            amount: vec![coin(1000, TEST_DENOM_NATIVE)],
+        }))
+        .unwrap();
+        let user = Addr::unchecked(TEST_USER);
+        let _res = app
+            .execute_contract(
+                user.clone(),
+                contract_address.clone(),
+                &deposit_msg,
+                &[coin(1000, TEST_DENOM_NATIVE)],
+            )
+            .unwrap();
+
+        approve_handler(
+            &mut app,
+            &contract_address,
+            &user,
+            ALLOWED_HANDLER,
+            TEST_DENOM_NATIVE,
+            "1000",
+        );
+
+        let msg = ExecuteMsg::Lock {
+            account: TEST_USER.into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+            expires: None,
+        };
+        let _res = app
+            .execute_contract(
+                Addr::unchecked(ALLOWED_HANDLER),
+                contract_address.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn emergency_pause_by_guardian() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let guardian = Addr::unchecked(TEST_USER2);
+
+        let admin_msg = AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        };
+        let msg = ExecuteMsg::Admin(admin_msg);
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let deposit_msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &deposit_msg,
+                &[coin(256000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let msg = ExecuteMsg::EmergencyPause {};
+        let err = app
+            .execute_contract(guardian.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized {}
+        );
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetGuardian {
+            guardian: Some(TEST_USER2.into()),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::EmergencyPause {};
+        let _res = app
+            .execute_contract(guardian.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: PauseStatusResponse = app
+            .wrap()
+            .query_wasm_smart(contract_address.clone(), &QueryMsg::PauseStatus {})
+            .unwrap();
+        assert_eq!(
+            res,
+            PauseStatusResponse {
+                status: PauseStatus::all(),
+                guardian: Some(TEST_USER2.into()),
+            }
+        );
+
+        let withdraw_msg = ExecuteMsg::WithdrawNative {
+            beneficiary: owner.clone().into_string(),
+            denom: TEST_DENOM_NATIVE.to_string(),
+            amount: "50000".into(),
+        };
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &withdraw_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Paused {}
+        );
+
+        // guardian cannot lift the pause
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetPauseStatus {
+            status: PauseStatus {
+                withdrawals: false,
+                ..PauseStatus::all()
+            },
+        });
+        let _err = app
+            .execute_contract(guardian.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &withdraw_msg, &[])
+            .unwrap();
+
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &deposit_msg,
+                &[coin(1000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Paused {}
+        );
+    }
+
+    #[test]
+    fn balance_changes_emit_events() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        });
+        let res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert_eq!(wasm_attribute(&res, "action"), "add_valid_currency");
+        assert_eq!(wasm_attribute(&res, "currency"), TEST_DENOM_NATIVE);
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
+        };
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(256000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+        assert_eq!(wasm_attribute(&res, "action"), "deposit");
+        assert_eq!(balance_change_attribute(&res, "action"), "deposit");
+        assert_eq!(balance_change_attribute(&res, "owner"), TEST_CREATOR);
+        assert_eq!(
+            balance_change_attribute(&res, "currency"),
+            TEST_DENOM_NATIVE
+        );
+        assert_eq!(balance_change_attribute(&res, "amount"), "256000");
+        assert_eq!(balance_change_attribute(&res, "available"), "256000");
+        assert_eq!(balance_change_attribute(&res, "locked"), "0");
+
+        approve_handler(
+            &mut app,
+            &contract_address,
+            &owner,
+            ALLOWED_HANDLER,
+            TEST_DENOM_NATIVE,
+            "256000",
+        );
+
+        let msg = ExecuteMsg::Lock {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "56000".into(),
+            expires: None,
+        };
+        let res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert_eq!(balance_change_attribute(&res, "action"), "lock");
+        assert_eq!(balance_change_attribute(&res, "handler"), ALLOWED_HANDLER);
+        assert_eq!(balance_change_attribute(&res, "lock_id"), "1");
+        assert_eq!(balance_change_attribute(&res, "available"), "200000");
+        assert_eq!(balance_change_attribute(&res, "locked"), "56000");
+
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "6000".into(),
+            beneficiary: TEST_USER.into(),
+            lock_id: None,
+            splits: None,
+        };
+        let res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert_eq!(wasm_attribute(&res, "action"), "transfer_locked");
+        assert_eq!(balance_change_attribute(&res, "beneficiary"), TEST_USER);
+        assert_eq!(balance_change_attribute(&res, "locked"), "50000");
+        assert_eq!(
+            balance_change_attribute(&res, "beneficiary_available"),
+            "6000"
+        );
+
+        // withdrawing to another address debits the sender
+        let msg = ExecuteMsg::WithdrawNative {
+            beneficiary: TEST_USER2.into(),
+            denom: TEST_DENOM_NATIVE.into(),
+            amount: "100000".into(),
+        };
+        let res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert_eq!(balance_change_attribute(&res, "action"), "withdraw");
+        assert_eq!(balance_change_attribute(&res, "owner"), TEST_CREATOR);
+        assert_eq!(balance_change_attribute(&res, "beneficiary"), TEST_USER2);
+        assert_eq!(balance_change_attribute(&res, "available"), "100000");
+
+        let res: CurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetBalance {
+                    account_owner: owner.clone().into(),
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.account.available, 100000);
+        assert_eq!(res.account.locked, 50000);
+    }
+
+    #[test]
+    fn hooks_notify_deposits_and_lock_releases() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+
+        let receiver_id = app.store_code(contract_mock_receiver());
+        let receiver = app
+            .instantiate_contract(receiver_id, owner.clone(), &Empty {}, &[], "receiver", None)
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: Some(DepositNotification {
+                contract: receiver.clone().into_string(),
+                payload: Some(Binary::from(b"order-1")),
+            }),
+            lock: None,
+        };
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(256000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+        assert_eq!(wasm_attribute(&res, "hook"), "deposit_received");
+
+        // a failing hook does not revert the deposit
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: Some(DepositNotification {
+                contract: receiver.clone().into_string(),
+                payload: Some(Binary::from(b"fail")),
+            }),
+            lock: None,
+        };
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(44000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+        assert!(res.events.iter().any(|event| event
+            .attributes
+            .iter()
+            .any(|attribute| attribute.value == "hook_failed")));
+
+        let res: CurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetBalance {
+                    account_owner: owner.clone().into(),
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.account.available, 300000);
+
+        // only authorized handlers register hooks
+        let msg = ExecuteMsg::SetHandlerHook {
+            hook: Some(receiver.clone().into_string()),
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized {}
+        );
+
+        let admin_msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &admin_msg, &[])
+            .unwrap();
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        approve_handler(
+            &mut app,
+            &contract_address,
+            &owner,
+            ALLOWED_HANDLER,
+            TEST_DENOM_NATIVE,
+            "100000",
+        );
+
+        let msg = ExecuteMsg::Lock {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "100000".into(),
+            expires: None,
+        };
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Unlock {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "40000".into(),
+            lock_id: None,
+        };
+        let res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert_eq!(wasm_attribute(&res, "hook"), "lock_released");
+
+        // revoking the handler drops its hook
+        for status in [false, true] {
+            let admin_msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+                target: ALLOWED_HANDLER.into(),
+                new_status: status,
+            });
+            let _res = app
+                .execute_contract(owner.clone(), contract_address.clone(), &admin_msg, &[])
+                .unwrap();
+        }
+        let res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert!(!res.events.iter().any(|event| event
+            .attributes
+            .iter()
+            .any(|attribute| attribute.key == "hook")));
+    }
+
+    #[test]
+    fn deposit_and_lock_in_one_call() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
+        let receiver_id = app.store_code(contract_mock_receiver());
+        let receiver = app
+            .instantiate_contract(receiver_id, owner.clone(), &Empty {}, &[], "receiver", None)
+            .unwrap();
+
+        for currency in [native_currency(), cw20_currency(&cw_address)] {
+            let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency { currency: currency });
+            let _res = app
+                .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        let deposit_lock = DepositLock {
+            handler: ALLOWED_HANDLER.into(),
+            amount: "100000".into(),
+            expires: None,
+        };
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: Some(deposit_lock.clone()),
+        };
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(256000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized {}
+        );
+
+        let admin_msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &admin_msg, &[])
+            .unwrap();
+        let hook_msg = ExecuteMsg::SetHandlerHook {
+            hook: Some(receiver.into_string()),
+        };
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &hook_msg, &[])
+            .unwrap();
+
+        // no allowance needed, the handler is notified of the lock
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(256000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+        assert_eq!(wasm_attribute(&res, "lock_id"), "1");
+        assert_eq!(wasm_attribute(&res, "hook"), "deposit_locked");
+
+        let res: CurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetBalance {
+                    account_owner: owner.clone().into(),
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.account.available, 156000);
+        assert_eq!(res.account.locked, 100000);
+
+        // only the deposited funds can be locked
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(50000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientFundsAvailableForLock {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                available: "50000".into(),
+                required: "100000".into(),
+            }
+        );
+
+        let msg = cw20::Cw20ExecuteMsg::Send {
+            contract: contract_address.clone().into_string(),
+            amount: Uint128::from(150000u128),
+            msg: to_binary(&ReceiveMsg::DepositAndLock {
+                beneficiary: Some(TEST_USER.into()),
+                lock: deposit_lock,
+                notify: None,
+            })
+            .unwrap(),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), cw_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: LockedBalancesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetLockedBalances {
+                    account_owner: TEST_USER.into(),
+                    currency_id: cw_address.clone().into_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res.locks,
+            vec![HandlerLockedBalance {
+                currency_id: cw_address.into_string(),
+                handler: ALLOWED_HANDLER.into(),
+                locked: 100000,
+            }]
+        );
+    }
+
+    #[test]
+    fn cw20_send_credits_sender_by_default() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: cw20_currency(&cw_address),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = cw20::Cw20ExecuteMsg::Send {
+            contract: contract_address.clone().into_string(),
+            amount: Uint128::from(30000u128),
+            msg: to_binary(&ReceiveMsg::Deposit { beneficiary: None }).unwrap(),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), cw_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = cw20::Cw20ExecuteMsg::Send {
+            contract: contract_address.clone().into_string(),
+            amount: Uint128::from(20000u128),
+            msg: to_binary(&ReceiveMsg::DepositFor {
+                beneficiary: TEST_USER.into(),
+                notify: None,
+            })
+            .unwrap(),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), cw_address.clone(), &msg, &[])
+            .unwrap();
+
+        for (account_owner, available) in [(TEST_CREATOR, 30000), (TEST_USER, 20000)] {
+            let res: CurrencyAccountResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_address.clone(),
+                    &QueryMsg::GetBalance {
+                        account_owner: account_owner.into(),
+                        currency_id: cw_address.clone().into_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(res.account.available, available);
+        }
+
+        // the beneficiary must be a valid address
+        let msg = cw20::Cw20ExecuteMsg::Send {
+            contract: contract_address.clone().into_string(),
+            amount: Uint128::from(20000u128),
+            msg: to_binary(&ReceiveMsg::DepositFor {
+                beneficiary: "".into(),
+                notify: None,
+            })
+            .unwrap(),
+        };
+        let err = app
+            .execute_contract(owner.clone(), cw_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::InvalidDepositBeneficiary {
+                beneficiary: "".into()
+            }
+            .to_string()
+        );
+
+        // a bare beneficiary string is no longer accepted
+        let msg = cw20::Cw20ExecuteMsg::Send {
+            contract: contract_address.clone().into_string(),
+            amount: Uint128::from(20000u128),
+            msg: to_binary(&TEST_USER.to_string()).unwrap(),
+        };
+        let _err = app
+            .execute_contract(owner.clone(), cw_address.clone(), &msg, &[])
+            .unwrap_err();
+    }
+
+    #[test]
+    fn withdraw_many_and_withdraw_all() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
+
+        for currency in [native_currency(), cw20_currency(&cw_address)] {
+            let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency { currency: currency });
+            let _res = app
+                .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(10000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let msg = cw20::Cw20ExecuteMsg::Send {
+            contract: contract_address.clone().into_string(),
+            amount: Uint128::from(8000u128),
+            msg: to_binary(&ReceiveMsg::Deposit { beneficiary: None }).unwrap(),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), cw_address.clone(), &msg, &[])
+            .unwrap();
+
+        let cw20_balance = |app: &App, address: &str| -> u128 {
+            let res: cw20::BalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cw_address.clone(),
+                    &cw20::Cw20QueryMsg::Balance {
+                        address: address.into(),
+                    },
+                )
+                .unwrap();
+            return res.balance.u128();
+        };
+
+        let msg = ExecuteMsg::WithdrawMany {
+            beneficiary: TEST_USER2.into(),
+            withdrawals: vec![
+                WithdrawItem {
+                    currency_identifier: TEST_DENOM_NATIVE.into(),
+                    amount: "1000".into(),
+                },
+                WithdrawItem {
+                    currency_identifier: cw_address.clone().into_string(),
+                    amount: "2000".into(),
+                },
+                WithdrawItem {
+                    currency_identifier: TEST_DENOM_NATIVE.into(),
+                    amount: "500".into(),
+                },
+            ],
+        };
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let balance = app
+            .wrap()
+            .query_balance(TEST_USER2, TEST_DENOM_NATIVE)
+            .unwrap();
+        assert_eq!(balance.amount.u128(), 1500);
+        assert_eq!(cw20_balance(&app, TEST_USER2), 2000);
+
+        // the whole batch fails when one withdrawal is not covered
+        let msg = ExecuteMsg::WithdrawMany {
+            beneficiary: TEST_USER2.into(),
+            withdrawals: vec![
+                WithdrawItem {
+                    currency_identifier: TEST_DENOM_NATIVE.into(),
+                    amount: "1000".into(),
+                },
+                WithdrawItem {
+                    currency_identifier: cw_address.clone().into_string(),
+                    amount: "7000".into(),
+                },
+            ],
+        };
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientFundsAvailableForCw20Withdrawal {
+                currency_identifier: cw_address.clone().into_string(),
+                available: "6000".into(),
+                required: "7000".into(),
+            }
+        );
+
+        let msg = ExecuteMsg::WithdrawAll {
+            beneficiary: TEST_USER2.into(),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let balance = app
+            .wrap()
+            .query_balance(TEST_USER2, TEST_DENOM_NATIVE)
+            .unwrap();
+        assert_eq!(balance.amount.u128(), 10000);
+        assert_eq!(cw20_balance(&app, TEST_USER2), 8000);
+
+        let res: AllCurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetAllBalances {
+                    account_owner: owner.clone().into(),
+                    start_after: None,
+                    limit: None,
+                    hide_zero: None,
+                },
+            )
+            .unwrap();
+        assert!(res
+            .accounts
+            .iter()
+            .all(|balance| balance.account.available == 0));
+    }
+
+    #[test]
+    fn withdraw_by_asset() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: None,
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(10000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        // the asset kind must match the accepted currency
+        let msg = ExecuteMsg::Withdraw {
+            asset: Asset {
+                info: AssetInfo::Cw20 {
+                    token_address: TEST_DENOM_NATIVE.into(),
+                },
+                amount: "4000".into(),
+            },
+            beneficiary: TEST_USER2.into(),
+        };
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Cw20NotAccepted {
+                token_address: TEST_DENOM_NATIVE.into()
+            }
+        );
+
+        let msg = ExecuteMsg::Withdraw {
+            asset: Asset {
+                info: AssetInfo::Native {
+                    denom: TEST_DENOM_NATIVE.into(),
+                },
+                amount: "4000".into(),
+            },
+            beneficiary: TEST_USER2.into(),
+        };
+        let res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert_eq!(wasm_attribute(&res, "action"), "withdraw");
+        assert_eq!(balance_change_attribute(&res, "available"), "6000");
+
+        let balance = app
+            .wrap()
+            .query_balance(TEST_USER2, TEST_DENOM_NATIVE)
+            .unwrap();
+        assert_eq!(balance.amount.u128(), 4000);
+    }
+
+    #[test]
+    fn transfer_locked_and_withdraw_pays_external_address() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: Some(DepositLock {
+                handler: ALLOWED_HANDLER.into(),
+                amount: "6000".into(),
+                expires: None,
+            }),
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(10000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let msg = ExecuteMsg::TransferLockedAndWithdraw {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "7000".into(),
+            beneficiary: TEST_USER2.into(),
+            lock_id: None,
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientFundsLockedForTransfer {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                available: "6000".into(),
+                required: "7000".into(),
+            }
+        );
+
+        let msg = ExecuteMsg::TransferLockedAndWithdraw {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "4000".into(),
+            beneficiary: TEST_USER2.into(),
+            lock_id: Some(1),
+        };
+        let res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert_eq!(balance_change_attribute(&res, "locked"), "2000");
+
+        let balance = app
+            .wrap()
+            .query_balance(TEST_USER2, TEST_DENOM_NATIVE)
+            .unwrap();
+        assert_eq!(balance.amount.u128(), 4000);
+
+        let res: CurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetBalance {
+                    account_owner: TEST_USER2.into(),
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.account.available, 0);
+    }
+
+    #[test]
+    fn settle_applies_all_legs_or_none() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        // buyer and seller both lock their side of the trade
+        for beneficiary in [TEST_CREATOR, TEST_USER] {
+            let msg = ExecuteMsg::DepositNative {
+                beneficiary: beneficiary.into(),
+                notify: None,
+                lock: Some(DepositLock {
+                    handler: ALLOWED_HANDLER.into(),
+                    amount: "4000".into(),
+                    expires: None,
+                }),
+            };
+            let _res = app
+                .execute_contract(
+                    owner.clone(),
+                    contract_address.clone(),
+                    &msg,
+                    &[coin(4000, TEST_DENOM_NATIVE.to_string())],
+                )
+                .unwrap();
+        }
+
+        let balance = |app: &App, account_owner: &str| -> CurrencyAccount {
+            let res: CurrencyAccountResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_address.clone(),
+                    &QueryMsg::GetBalance {
+                        account_owner: account_owner.into(),
+                        currency_id: TEST_DENOM_NATIVE.into(),
+                    },
+                )
+                .unwrap();
+            return res.account;
+        };
+
+        let mut legs = vec![
+            SettlementLeg::TransferLocked {
+                account: TEST_CREATOR.into(),
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: "3000".into(),
+                beneficiary: TEST_USER.into(),
+                lock_id: Some(1),
+                splits: None,
+            },
+            SettlementLeg::TransferLocked {
+                account: TEST_USER.into(),
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: "3500".into(),
+                beneficiary: TEST_CREATOR.into(),
+                lock_id: Some(2),
+                splits: None,
+            },
+            SettlementLeg::TransferLocked {
+                account: TEST_USER.into(),
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: "500".into(),
+                beneficiary: TEST_USER2.into(),
+                lock_id: Some(2),
+                splits: None,
+            },
+            SettlementLeg::Unlock {
+                account: TEST_CREATOR.into(),
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: "2000".into(),
+                lock_id: Some(1),
+            },
+        ];
+
+        // the last leg exceeds the lock, nothing is applied
+        let msg = ExecuteMsg::Settle { legs: legs.clone() };
+        let _err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            balance(&app, TEST_CREATOR),
+            CurrencyAccount {
+                available: 0,
+                locked: 4000,
+            }
+        );
+        assert_eq!(balance(&app, TEST_USER2).available, 0);
+
+        legs[3] = SettlementLeg::Unlock {
+            account: TEST_CREATOR.into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+            lock_id: Some(1),
+        };
+        let msg = ExecuteMsg::Settle { legs: legs };
+        let res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let legs: Vec<String> = res
+            .events
+            .iter()
+            .filter(|event| event.ty == "wasm-balance_change")
+            .flat_map(|event| event.attributes.iter())
+            .filter(|attribute| attribute.key == "leg")
+            .map(|attribute| attribute.value.clone())
+            .collect();
+        assert_eq!(legs, vec!["0", "1", "2", "3"]);
+
+        assert_eq!(
+            balance(&app, TEST_CREATOR),
+            CurrencyAccount {
+                available: 4500,
+                locked: 0,
+            }
+        );
+        assert_eq!(
+            balance(&app, TEST_USER),
+            CurrencyAccount {
+                available: 3000,
+                locked: 0,
+            }
+        );
+        assert_eq!(balance(&app, TEST_USER2).available, 500);
+    }
+
+    #[test]
+    fn fees_on_transfer_locked_and_withdraw() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+        let user2 = Addr::unchecked(TEST_USER2);
+        let collector = "fee_collector";
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetFeeSchedule {
+            currency_id: TEST_DENOM_NATIVE.into(),
+            schedule: FeeSchedule {
+                transfer_locked: Fee {
+                    bps: 10001,
+                    flat: 0,
+                },
+                withdraw: Fee::default(),
+            },
+        });
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InvalidFee {}
+        );
+
+        let schedule = FeeSchedule {
+            transfer_locked: Fee { bps: 100, flat: 0 },
+            withdraw: Fee { bps: 0, flat: 50 },
+        };
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetFeeSchedule {
+            currency_id: TEST_DENOM_NATIVE.into(),
+            schedule: schedule.clone(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetFeeCollector {
+            collector: Some(collector.into()),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: Some(DepositLock {
+                handler: ALLOWED_HANDLER.into(),
+                amount: "6000".into(),
+                expires: None,
+            }),
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(10000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        // 1% of the locked transfer goes to the collector
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "5000".into(),
+            beneficiary: TEST_USER2.into(),
+            lock_id: None,
+            splits: None,
+        };
+        let res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert_eq!(balance_change_attribute(&res, "fee"), "50");
+        assert_eq!(
+            balance_change_attribute(&res, "beneficiary_available"),
+            "4950"
+        );
+
+        // flat fee of 50 on withdrawals
+        let msg = ExecuteMsg::Withdraw {
+            asset: Asset {
+                info: AssetInfo::Native {
+                    denom: TEST_DENOM_NATIVE.into(),
+                },
+                amount: "1000".into(),
+            },
+            beneficiary: TEST_USER2.into(),
+        };
+        let res = app
+            .execute_contract(user2.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert_eq!(balance_change_attribute(&res, "fee"), "50");
+        assert_eq!(balance_change_attribute(&res, "available"), "3950");
+
+        let balance = app
+            .wrap()
+            .query_balance(TEST_USER2, TEST_DENOM_NATIVE)
+            .unwrap();
+        assert_eq!(balance.amount.u128(), 950);
+
+        let res: CurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetBalance {
+                    account_owner: collector.into(),
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.account.available, 100);
+
+        let res: FeesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::Fees {
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res,
+            FeesResponse {
+                collector: Some(collector.into()),
+                schedule: schedule,
+                accrued: 100,
+            }
+        );
+
+        // a fee larger than the amount is rejected
+        let msg = ExecuteMsg::Withdraw {
+            asset: Asset {
+                info: AssetInfo::Native {
+                    denom: TEST_DENOM_NATIVE.into(),
+                },
+                amount: "10".into(),
+            },
+            beneficiary: TEST_USER2.into(),
+        };
+        let err = app
+            .execute_contract(user2.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::FeeExceedsAmount {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: "10".into(),
+                fee: "50".into(),
+            }
+        );
+
+        // a flat fee at the limit of u128 does not overflow
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetFeeSchedule {
+            currency_id: TEST_DENOM_NATIVE.into(),
+            schedule: FeeSchedule {
+                transfer_locked: Fee::default(),
+                withdraw: Fee {
+                    bps: 100,
+                    flat: u128::MAX,
+                },
+            },
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        // balances not covering the fee are left out of a full withdrawal
+        let msg = ExecuteMsg::WithdrawAll {
+            beneficiary: TEST_USER2.into(),
+        };
+        let res = app
+            .execute_contract(user2.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert!(!res
+            .events
+            .iter()
+            .any(|event| event.ty == "wasm-balance_change"));
+
+        let res: CurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetBalance {
+                    account_owner: TEST_USER2.into(),
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.account.available, 3950);
+
+        // the collector withdraws the collected fees without paying a fee
+        let msg = ExecuteMsg::WithdrawAll {
+            beneficiary: collector.into(),
+        };
+        let res = app
+            .execute_contract(
+                Addr::unchecked(collector),
+                contract_address.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+        assert_eq!(balance_change_attribute(&res, "available"), "0");
+
+        let balance = app
+            .wrap()
+            .query_balance(collector, TEST_DENOM_NATIVE)
+            .unwrap();
+        assert_eq!(balance.amount.u128(), 100);
+    }
+
+    #[test]
+    fn transfer_locked_with_splits() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: Some(DepositLock {
+                handler: ALLOWED_HANDLER.into(),
+                amount: "10000".into(),
+                expires: None,
+            }),
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(10000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+            beneficiary: TEST_USER2.into(),
+            lock_id: None,
+            splits: Some(vec![
+                Split {
+                    recipient: ALLOWED_HANDLER.into(),
+                    share: SplitShare::Bps { bps: 6000 },
+                },
+                Split {
+                    recipient: TEST_USER.into(),
+                    share: SplitShare::Amount {
+                        amount: "500".into(),
+                    },
+                },
+            ]),
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::SplitExceedsAmount {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: "1000".into(),
+                split_total: "1100".into(),
+            }
+        );
+
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+            beneficiary: TEST_USER2.into(),
+            lock_id: None,
+            splits: Some(vec![Split {
+                recipient: ALLOWED_HANDLER.into(),
+                share: SplitShare::Bps { bps: 10001 },
+            }]),
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InvalidSplit {}
+        );
+
+        // the beneficiary is validated like the split recipients
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+            beneficiary: "x".into(),
+            lock_id: None,
+            splits: Some(vec![Split {
+                recipient: TEST_USER.into(),
+                share: SplitShare::Bps { bps: 250 },
+            }]),
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InvalidTransferRecipient {
+                recipient: "x".into(),
+            }
+        );
+
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+            beneficiary: TEST_USER2.into(),
+            lock_id: None,
+            splits: Some(vec![Split {
+                recipient: "x".into(),
+                share: SplitShare::Bps { bps: 250 },
+            }]),
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InvalidTransferRecipient {
+                recipient: "x".into(),
+            }
+        );
+
+        // split amounts adding up past u128 are rejected without overflowing
+        let large_split = Split {
+            recipient: TEST_USER.into(),
+            share: SplitShare::Amount {
+                amount: u128::MAX.to_string(),
+            },
+        };
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+            beneficiary: TEST_USER2.into(),
+            lock_id: None,
+            splits: Some(vec![large_split.clone(), large_split]),
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::SplitExceedsAmount {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: "1000".into(),
+                split_total: (Uint256::from(u128::MAX) + Uint256::from(u128::MAX)).to_string(),
+            }
+        );
+
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+            beneficiary: TEST_USER2.into(),
+            lock_id: None,
+            splits: Some(vec![
+                Split {
+                    recipient: ALLOWED_HANDLER.into(),
+                    share: SplitShare::Bps { bps: 250 },
+                },
+                Split {
+                    recipient: TEST_USER.into(),
+                    share: SplitShare::Amount {
+                        amount: "100".into(),
+                    },
+                },
+            ]),
+        };
+        let res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        let amounts: Vec<String> = res
+            .events
+            .iter()
+            .filter(|event| event.ty == "wasm-balance_change")
+            .map(|event| {
+                event
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.key == "amount")
+                    .unwrap()
+                    .value
+                    .clone()
+            })
+            .collect();
+        assert_eq!(amounts, vec!["875", "25", "100"]);
+
+        for (recipient, expected) in [
+            (TEST_USER2, 875),
+            (ALLOWED_HANDLER, 25),
+            (TEST_USER, 100),
+            (TEST_CREATOR, 0),
+        ] {
+            let res: CurrencyAccountResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_address.clone(),
+                    &QueryMsg::GetBalance {
+                        account_owner: recipient.into(),
+                        currency_id: TEST_DENOM_NATIVE.into(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(res.account.available, expected);
+        }
+    }
+
+    #[test]
+    fn paginated_balance_queries() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
+
+        for currency in [native_currency(), cw20_currency(&cw_address)] {
+            let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency { currency: currency });
+            let _res = app
+                .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        for (beneficiary, amount) in [(TEST_CREATOR, 1000), (TEST_USER, 2000), (TEST_USER2, 3000)] {
+            let msg = ExecuteMsg::DepositNative {
+                beneficiary: beneficiary.into(),
+                notify: None,
+                lock: None,
+            };
+            let _res = app
+                .execute_contract(
+                    owner.clone(),
+                    contract_address.clone(),
+                    &msg,
+                    &[coin(amount, TEST_DENOM_NATIVE.to_string())],
+                )
+                .unwrap();
+        }
+
+        let native_balance = CurrencyBalance {
+            currency_id: TEST_DENOM_NATIVE.into(),
+            account: CurrencyAccount {
+                available: 1000,
+                locked: 0,
+            },
+        };
+        let cw20_balance = CurrencyBalance {
+            currency_id: cw_address.to_string(),
+            account: CurrencyAccount::new(),
+        };
+
+        let res: AllCurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetAllBalances {
+                    account_owner: owner.clone().into(),
+                    start_after: None,
+                    limit: Some(1),
+                    hide_zero: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.accounts, vec![cw20_balance.clone()]);
+
+        let res: AllCurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetAllBalances {
+                    account_owner: owner.clone().into(),
+                    start_after: Some(cw_address.to_string()),
+                    limit: None,
+                    hide_zero: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.accounts, vec![native_balance.clone()]);
+
+        let res: AllCurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetAllBalances {
+                    account_owner: owner.clone().into(),
+                    start_after: None,
+                    limit: None,
+                    hide_zero: Some(true),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.accounts, vec![native_balance]);
+
+        let res: CurrencyHoldersResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::ListAccountsForCurrency {
+                    currency: TEST_DENOM_NATIVE.into(),
+                    start_after: None,
+                    limit: Some(2),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res.accounts,
+            vec![
+                HolderBalance {
+                    owner: TEST_CREATOR.into(),
+                    account: CurrencyAccount {
+                        available: 1000,
+                        locked: 0,
+                    },
+                },
+                HolderBalance {
+                    owner: TEST_USER.into(),
+                    account: CurrencyAccount {
+                        available: 2000,
+                        locked: 0,
+                    },
+                },
+            ]
+        );
+
+        // emptied accounts are no longer listed
+        let msg = ExecuteMsg::WithdrawAll {
+            beneficiary: owner.clone().into(),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: CurrencyHoldersResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::ListAccountsForCurrency {
+                    currency: TEST_DENOM_NATIVE.into(),
+                    start_after: Some(TEST_USER.into()),
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res.accounts
+                .iter()
+                .map(|holder| holder.owner.as_str())
+                .collect::<Vec<&str>>(),
+            vec![TEST_USER2]
+        );
+
+        let res: CurrencyHoldersResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::ListAccountsForCurrency {
+                    currency: TEST_DENOM_NATIVE.into(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.accounts.len(), 2);
+        assert_eq!(res.accounts[0].owner, TEST_USER);
+    }
+
+    #[test]
+    fn config_and_query_errors() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        for (handler, status) in [(ALLOWED_HANDLER, true), (ALLOWED_HANDLER2, false)] {
+            let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+                target: handler.into(),
+                new_status: status,
+            });
+            let _res = app
+                .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        let res: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(contract_address.clone(), &QueryMsg::Config {})
+            .unwrap();
+        assert_eq!(res.admin, Some(TEST_CREATOR.into()));
+        assert_eq!(
+            res.currencies,
+            vec![CurrencyResponse {
+                currency: native_currency(),
+                status: CurrencyStatus::Active,
+            }]
+        );
+        assert_eq!(res.handlers, vec![ALLOWED_HANDLER.to_string()]);
+
+        // accepted currency never deposited is a zero balance
+        let res: CurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetBalance {
+                    account_owner: TEST_USER.into(),
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.account, CurrencyAccount::new());
+
+        let err = app
+            .wrap()
+            .query_wasm_smart::<CurrencyAccountResponse>(
+                contract_address.clone(),
+                &QueryMsg::GetBalance {
+                    account_owner: TEST_USER.into(),
+                    currency_id: "unknown".into(),
+                },
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown"));
+
+        let _err = app
+            .wrap()
+            .query_wasm_smart::<AllCurrencyAccountResponse>(
+                contract_address.clone(),
+                &QueryMsg::GetAllBalances {
+                    account_owner: "x".into(),
+                    start_after: None,
+                    limit: None,
+                    hide_zero: None,
+                },
+            )
+            .unwrap_err();
+    }
+
+    #[test]
+    fn totals_track_deposits_and_holdings() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
+
+        for currency in [native_currency(), cw20_currency(&cw_address)] {
+            let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency { currency: currency });
+            let _res = app
+                .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: TEST_USER.into(),
+            notify: None,
+            lock: Some(DepositLock {
+                handler: ALLOWED_HANDLER.into(),
+                amount: "4000".into(),
+                expires: None,
+            }),
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(10000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let msg = cw20::Cw20ExecuteMsg::Send {
+            contract: contract_address.clone().into_string(),
+            amount: Uint128::from(3000u128),
+            msg: to_binary(&ReceiveMsg::Deposit { beneficiary: None }).unwrap(),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), cw_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: TEST_USER2.into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+        };
+        let _res = app
+            .execute_contract(
+                Addr::unchecked(TEST_USER),
+                contract_address.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+        let msg = ExecuteMsg::Withdraw {
+            asset: Asset {
+                info: AssetInfo::Cw20 {
+                    token_address: cw_address.to_string(),
+                },
+                amount: "500".into(),
+            },
+            beneficiary: TEST_CREATOR.into(),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: TotalsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::Totals {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res.totals,
+            vec![
+                CurrencyBalance {
+                    currency_id: cw_address.to_string(),
+                    account: CurrencyAccount {
+                        available: 2500,
+                        locked: 0,
+                    },
+                },
+                CurrencyBalance {
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                    account: CurrencyAccount {
+                        available: 6000,
+                        locked: 4000,
+                    },
+                },
+            ]
+        );
+
+        // funds sent without a deposit are held but not accounted
+        app.execute(
+            owner.clone(),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: contract_address.to_string(),
+                amount: vec![coin(700, TEST_DENOM_NATIVE)],
+            }),
+        )
+        .unwrap();
+
+        let res: SolvencyResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::SolvencyCheck {
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res,
+            SolvencyResponse {
+                currency_id: TEST_DENOM_NATIVE.into(),
+                deposits: CurrencyAccount {
+                    available: 6000,
+                    locked: 4000,
+                },
+                held: 10700,
+                solvent: true,
+            }
+        );
+
+        let res: SolvencyResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::SolvencyCheck {
+                    currency_id: cw_address.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.held, 2500);
+        assert!(res.solvent);
+    }
+
+    #[test]
+    fn sweep_surplus_leaves_deposits() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
+
+        for currency in [native_currency(), cw20_currency(&cw_address)] {
+            let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency { currency: currency });
+            let _res = app
+                .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: TEST_USER.into(),
+            notify: None,
+            lock: None,
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(10000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SweepSurplus {
+            currency: TEST_DENOM_NATIVE.into(),
+            recipient: TEST_USER2.into(),
+        });
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::NoSurplus {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+            }
+        );
+
+        // sent without a deposit message, so never credited
+        app.execute(
+            owner.clone(),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: contract_address.to_string(),
+                amount: vec![coin(700, TEST_DENOM_NATIVE)],
+            }),
+        )
+        .unwrap();
+        let msg = cw20::Cw20ExecuteMsg::Transfer {
+            recipient: contract_address.to_string(),
+            amount: Uint128::from(300u128),
+        };
+        let _res = app
+            .execute_contract(owner.clone(), cw_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SweepSurplus {
+            currency: TEST_DENOM_NATIVE.into(),
+            recipient: TEST_USER2.into(),
+        });
+        let err = app
+            .execute_contract(
+                Addr::unchecked(TEST_USER),
+                contract_address.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized {}
+        );
+
+        let res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert_eq!(wasm_attribute(&res, "amount"), "700");
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SweepSurplus {
+            currency: cw_address.to_string(),
+            recipient: TEST_USER2.into(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let balance = app
+            .wrap()
+            .query_balance(TEST_USER2, TEST_DENOM_NATIVE)
+            .unwrap();
+        assert_eq!(balance.amount.u128(), 700);
+        let res: cw20::BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_address.clone(),
+                &cw20::Cw20QueryMsg::Balance {
+                    address: TEST_USER2.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.balance.u128(), 300);
+
+        let res: SolvencyResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::SolvencyCheck {
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.held, 10000);
+        assert_eq!(res.deposits.available, 10000);
+    }
+
+    #[test]
+    fn handler_registry_limits_operations() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+        let cw_address = create_cw20(&mut app, TEST_DENOM_CW20, "cwtest", TEST_CREATOR, 5000000);
+
+        for currency in [native_currency(), cw20_currency(&cw_address)] {
+            let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency { currency: currency });
+            let _res = app
+                .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        // currencies and caps refer to accepted currencies only
+        for (currencies, caps) in [
+            (Some(vec!["unknown".to_string()]), vec![]),
+            (
+                None,
+                vec![HandlerCap {
+                    currency_identifier: "unknown".into(),
+                    amount: 5000,
+                    period: Duration::Time(3600),
+                }],
+            ),
+        ] {
+            let msg = ExecuteMsg::Admin(AdminExecuteMsg::RegisterHandler {
+                handler: ALLOWED_HANDLER.into(),
+                record: HandlerRecord {
+                    name: "marketplace".into(),
+                    description: None,
+                    operations: vec![HandlerOperation::Lock],
+                    currencies: currencies,
+                    caps: caps,
+                },
+            });
+            let err = app
+                .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::CurrencyNotAccepted {
+                    currency_identifier: "unknown".into(),
+                }
+            );
+        }
+
+        let record = HandlerRecord {
+            name: "marketplace".into(),
+            description: Some("settles sales".into()),
+            operations: vec![HandlerOperation::Lock, HandlerOperation::TransferLocked],
+            currencies: Some(vec![TEST_DENOM_NATIVE.into()]),
+            caps: vec![HandlerCap {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                amount: 5000,
+                period: Duration::Time(3600),
+            }],
+        };
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::RegisterHandler {
+            handler: ALLOWED_HANDLER.into(),
+            record: record.clone(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        // authorizing a registered handler keeps its limits
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: HandlerResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetHandler {
+                    handler: ALLOWED_HANDLER.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.record, record);
+
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: Some(DepositLock {
+                handler: ALLOWED_HANDLER.into(),
+                amount: "8000".into(),
+                expires: None,
+            }),
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(10000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        let msg = ExecuteMsg::Unlock {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: "1000".into(),
+            lock_id: None,
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::HandlerOperationNotAllowed {
+                operation: "unlock".into(),
+            }
+        );
+
+        let msg = ExecuteMsg::Settle { legs: vec![] };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::HandlerOperationNotAllowed {
+                operation: "settle".into(),
+            }
+        );
+
+        let msg = ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: cw_address.to_string(),
+            amount: "1000".into(),
+            beneficiary: TEST_USER2.into(),
+            lock_id: None,
+            splits: None,
+        };
+        let err = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::HandlerCurrencyNotAllowed {
+                currency_identifier: cw_address.to_string(),
+            }
+        );
+
+        // the deposit lock of the owner does not count against the 5000 cap, transfers do
+        let transfer = |amount: &str| ExecuteMsg::TransferLocked {
+            account: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: amount.into(),
+            beneficiary: TEST_USER2.into(),
+            lock_id: None,
+            splits: None,
+        };
+        let _res = app
+            .execute_contract(
+                handler.clone(),
+                contract_address.clone(),
+                &transfer("3000"),
+                &[],
+            )
+            .unwrap();
+        let err = app
+            .execute_contract(
+                handler.clone(),
+                contract_address.clone(),
+                &transfer("3000"),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::HandlerCapExceeded {
+                currency_identifier: TEST_DENOM_NATIVE.into(),
+                available: "2000".into(),
+                required: "3000".into(),
+            }
+        );
+        let _res = app
+            .execute_contract(
+                handler.clone(),
+                contract_address.clone(),
+                &transfer("2000"),
+                &[],
+            )
+            .unwrap();
+
+        app.update_block(|block| block.time = block.time.plus_seconds(3600));
+        let _res = app
+            .execute_contract(
+                handler.clone(),
+                contract_address.clone(),
+                &transfer("3000"),
+                &[],
+            )
+            .unwrap();
+
+        // revoking removes the registration
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: false,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER2.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: HandlersResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::ListHandlers {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res.handlers,
+            vec![HandlerResponse {
+                handler: ALLOWED_HANDLER2.into(),
+                record: HandlerRecord::unrestricted(ALLOWED_HANDLER2.into()),
+            }]
+        );
+
+        let err = app
+            .execute_contract(
+                handler.clone(),
+                contract_address.clone(),
+                &transfer("100"),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized {}
+        );
+    }
+
+    #[test]
+    fn broken_hook_cannot_block_reclaim() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+        let handler = Addr::unchecked(ALLOWED_HANDLER);
+
+        let sub_msg = SafeDepositHookMsg::LockReleased {
+            owner: owner.clone().into(),
+            currency_identifier: TEST_DENOM_NATIVE.into(),
+            amount: Uint128::from(1u128),
+            lock_id: None,
+        }
+        .into_sub_msg(handler.clone().into())
+        .unwrap();
+        assert_eq!(sub_msg.gas_limit, Some(HOOK_GAS_LIMIT));
+
+        let receiver_id = app.store_code(contract_mock_broken_receiver());
+        let receiver = app
+            .instantiate_contract(receiver_id, owner.clone(), &Empty {}, &[], "receiver", None)
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::SetHandlerHook {
+            hook: Some(receiver.into_string()),
+        };
+        let _res = app
+            .execute_contract(handler.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let expiration_height = app.block_info().height + 10;
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: Some(DepositLock {
+                handler: ALLOWED_HANDLER.into(),
+                amount: "5000".into(),
+                expires: Some(Expiration::AtHeight(expiration_height)),
+            }),
+        };
+        let _res = app
+            .execute_contract(
+                owner.clone(),
+                contract_address.clone(),
+                &msg,
+                &[coin(5000, TEST_DENOM_NATIVE.to_string())],
+            )
+            .unwrap();
+
+        app.update_block(|block| block.height = expiration_height);
+
+        let msg = ExecuteMsg::ReclaimExpiredLock { lock_id: 1 };
+        let res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+        assert!(res.events.iter().any(|event| event
+            .attributes
+            .iter()
+            .any(|attribute| attribute.value == "hook_failed")));
+        assert_eq!(balance_change_attribute(&res, "available"), "5000");
+    }
+
+    #[test]
+    fn migrate_releases_untracked_locks() {
+        let mut deps = mock_dependencies();
+        let owner = String::from("depositor");
+        let currency_id = native_currency().identifier();
+
+        // locked before HANDLER_LOCKS existed, no handler is able to release it
+        BALANCES
+            .save(
+                deps.as_mut().storage,
+                (owner.clone(), currency_id.clone()),
+                &CurrencyAccount {
+                    available: 1000,
+                    locked: 4000,
+                },
+            )
+            .unwrap();
+        HANDLER_LOCKS
+            .save(
+                deps.as_mut().storage,
+                (
+                    owner.clone(),
+                    currency_id.clone(),
+                    Addr::unchecked("handler"),
+                ),
+                &1500,
+            )
+            .unwrap();
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_currencies: None,
+            },
+        )
+        .unwrap();
+
+        let account =
+            state_reads::get_currency_account(deps.as_ref(), owner.clone(), currency_id.clone())
+                .unwrap();
+        assert_eq!(account.available, 3500);
+        assert_eq!(account.locked, 1500);
+        let total = state_reads::get_total_deposits(deps.as_ref(), currency_id.clone()).unwrap();
+        assert_eq!(total.available, 3500);
+        assert_eq!(total.locked, 1500);
+
+        // running it again leaves tracked locks alone
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_currencies: None,
+            },
+        )
+        .unwrap();
+        let account = state_reads::get_currency_account(deps.as_ref(), owner, currency_id).unwrap();
+        assert_eq!(account.available, 3500);
+        assert_eq!(account.locked, 1500);
+    }
+
+    #[test]
+    fn migrate_registers_legacy_currencies() {
+        let mut deps = mock_dependencies();
+        let token_address = Addr::unchecked("token");
+        LEGACY_VALID_CURRENCIES
+            .save(
+                deps.as_mut().storage,
+                &vec![
+                    native_currency().identifier(),
+                    cw20_currency(&token_address).identifier(),
+                ],
+            )
+            .unwrap();
+
+        // every legacy identifier needs its metadata
+        let err = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_currencies: Some(vec![native_currency()]),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MissingCurrencyMetadata {
+                currency_identifier: cw20_currency(&token_address).identifier(),
+            }
+        );
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_currencies: Some(vec![native_currency(), cw20_currency(&token_address)]),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            VALID_CURRENCIES
+                .load(deps.as_ref().storage, native_currency().identifier())
+                .unwrap(),
+            native_currency()
+        );
+        assert_eq!(
+            VALID_CURRENCIES
+                .load(
+                    deps.as_ref().storage,
+                    cw20_currency(&token_address).identifier()
+                )
+                .unwrap(),
+            cw20_currency(&token_address)
+        );
+        assert!(LEGACY_VALID_CURRENCIES
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+
+        // later migrations do not need the metadata again
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_currencies: None,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn locks_of_deprecated_handler_are_reclaimed() {
+        let (mut app, contract_address) = setup_env();
+
+        let owner = Addr::unchecked(TEST_CREATOR);
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::AddValidCurrency {
+            currency: native_currency(),
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: true,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        // two locks that never expire
+        let msg = ExecuteMsg::DepositNative {
+            beneficiary: owner.clone().into(),
+            notify: None,
+            lock: Some(DepositLock {
+                handler: ALLOWED_HANDLER.into(),
+                amount: "1000".into(),
+                expires: None,
+            }),
+        };
+        for _ in 0..2 {
+            let _res = app
+                .execute_contract(
+                    owner.clone(),
+                    contract_address.clone(),
+                    &msg,
+                    &[coin(1000, TEST_DENOM_NATIVE.to_string())],
+                )
+                .unwrap();
+        }
+
+        let msg = ExecuteMsg::ReclaimExpiredLock { lock_id: 1 };
+        let err = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::LockNotExpired { lock_id: 1 }
+        );
+
+        // registered without an operation releasing locked value
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::RegisterHandler {
+            handler: ALLOWED_HANDLER.into(),
+            record: HandlerRecord {
+                name: "deprecated".into(),
+                description: None,
+                operations: vec![HandlerOperation::Lock],
+                currencies: None,
+                caps: vec![],
+            },
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::ReclaimExpiredLock { lock_id: 1 };
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        // revoked
+        let msg = ExecuteMsg::Admin(AdminExecuteMsg::SetAuthorizationStatus {
+            target: ALLOWED_HANDLER.into(),
+            new_status: false,
+        });
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let msg = ExecuteMsg::ReclaimExpiredLock { lock_id: 2 };
+        let _res = app
+            .execute_contract(owner.clone(), contract_address.clone(), &msg, &[])
+            .unwrap();
+
+        let res: CurrencyAccountResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_address.clone(),
+                &QueryMsg::GetBalance {
+                    account_owner: owner.clone().into(),
+                    currency_id: TEST_DENOM_NATIVE.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.account.available, 2000);
+        assert_eq!(res.account.locked, 0);
+    }
 }
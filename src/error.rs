@@ -15,9 +15,18 @@ pub enum ContractError {
     #[error("Invalid Deposit Beneficiary {beneficiary}")]
     InvalidDepositBeneficiary { beneficiary: String },
 
+    #[error("Invalid Transfer Recipient {recipient}")]
+    InvalidTransferRecipient { recipient: String },
+
+    #[error("Paused")]
+    Paused {},
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("No pending admin proposal")]
+    NoPendingAdmin {},
+
     #[error("Storage Item does not exist: {identifier}")]
     StorageItemNotExist { identifier: String },
 
@@ -27,6 +36,27 @@ pub enum ContractError {
     #[error("Cw20 not Accepted {token_address}")]
     Cw20NotAccepted { token_address: String },
 
+    #[error("Currency not Accepted {currency_identifier}")]
+    CurrencyNotAccepted { currency_identifier: String },
+
+    #[error("Operation {operation} disabled for currency {currency_identifier}")]
+    CurrencyOperationDisabled {
+        currency_identifier: String,
+        operation: String,
+    },
+
+    #[error("Currency {currency_identifier} is still held by accounts")]
+    CurrencyStillHeld { currency_identifier: String },
+
+    #[error("No surplus of {currency_identifier} held beyond deposits")]
+    NoSurplus { currency_identifier: String },
+
+    #[error("Currency {currency_identifier} is registered with another kind")]
+    CurrencyKindMismatch { currency_identifier: String },
+
+    #[error("Missing currency metadata for {currency_identifier}")]
+    MissingCurrencyMetadata { currency_identifier: String },
+
     #[error("Not enough funds available for lock {currency_identifier} (available: {available}, required: {required})")]
     InsufficientFundsAvailableForLock {
         currency_identifier: String,
@@ -48,6 +78,13 @@ pub enum ContractError {
         required: String,
     },
 
+    #[error("Not enough funds available for transfers {currency_identifier} (available: {available}, required: {required})")]
+    InsufficientFundsAvailableForTransfer {
+        currency_identifier: String,
+        available: String,
+        required: String,
+    },
+
     #[error("Not enough funds locked for native withdrawal {currency_identifier} (available: {available}, required: {required})")]
     InsufficientFundsAvailableForNativeWithdrawal {
         currency_identifier: String,
@@ -68,18 +105,77 @@ pub enum ContractError {
         currency_identifier: String,
     },
 
+    #[error("No Lock found for id {lock_id}")]
+    LockNotFound { lock_id: u64 },
+
+    #[error("Lock {lock_id} does not match the pair ({owner}, {currency_identifier})")]
+    LockMismatch {
+        lock_id: u64,
+        owner: String,
+        currency_identifier: String,
+    },
+
     #[error("Cannot set to own account")]
     CannotSetOwnAccount {},
 
     #[error("Invalid zero amount")]
     InvalidZeroAmount {},
 
-    #[error("Allowance is expired")]
+    #[error("Lock or allowance is expired")]
     Expired {},
 
+    #[error("Lock {lock_id} is not expired")]
+    LockNotExpired { lock_id: u64 },
+
     #[error("No allowance for this account")]
     NoAllowance {},
 
+    #[error("Allowance exceeded for lock {currency_identifier} (available: {available}, required: {required})")]
+    InsufficientAllowance {
+        currency_identifier: String,
+        available: String,
+        required: String,
+    },
+
+    #[error("Handler {handler} is not registered")]
+    HandlerNotFound { handler: String },
+
+    #[error("Operation {operation} not allowed for handler")]
+    HandlerOperationNotAllowed { operation: String },
+
+    #[error("Currency {currency_identifier} not allowed for handler")]
+    HandlerCurrencyNotAllowed { currency_identifier: String },
+
+    #[error("Invalid handler cap for {currency_identifier}")]
+    InvalidHandlerCap { currency_identifier: String },
+
+    #[error("Handler cap exceeded for {currency_identifier} (available: {available}, required: {required})")]
+    HandlerCapExceeded {
+        currency_identifier: String,
+        available: String,
+        required: String,
+    },
+
+    #[error("Invalid fee, basis points cannot exceed 10000")]
+    InvalidFee {},
+
+    #[error("Fee {fee} exceeds amount {amount} of {currency_identifier}")]
+    FeeExceedsAmount {
+        currency_identifier: String,
+        amount: String,
+        fee: String,
+    },
+
+    #[error("Invalid split, shares cannot be zero and basis points cannot exceed 10000")]
+    InvalidSplit {},
+
+    #[error("Splits of {split_total} exceed amount {amount} of {currency_identifier}")]
+    SplitExceedsAmount {
+        currency_identifier: String,
+        amount: String,
+        split_total: String,
+    },
+
     // payable
     #[error("Payable Contract or Method")]
     RequiresFunds {},
@@ -93,3 +189,13 @@ pub enum ContractError {
     #[error("Funds amount invalid")]
     InvalidFundsAmount {},
 }
+
+// queries return StdError, contract errors other than StdError keep their message
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            ContractError::Std(err) => return err,
+            err => return StdError::generic_err(err.to_string()),
+        }
+    }
+}
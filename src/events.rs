@@ -0,0 +1,174 @@
+//! Event schema emitted by the contract, indexers can rely on these names.
+//!
+//! Every state-changing message sets an `action` attribute on the `wasm` event.
+//! Each change of a depositor balance additionally emits a `wasm-balance_change`
+//! event with the following attributes:
+//!
+//! - `action`: always, see [`BalanceAction`]
+//! - `owner`: always, account whose funds are used or credited
+//! - `currency`, `amount`: always
+//! - `available`, `locked`: always, balances of `owner` after the change
+//! - `beneficiary`: when funds go to another address
+//! - `depositor`: for deposits, address that funded the deposit, which may differ from `owner`
+//! - `beneficiary_available`, `beneficiary_locked`: when the beneficiary is credited inside the vault
+//! - `handler`: for operations on locked funds
+//! - `lock_id`: when a single lock is targeted
+//! - `leg`: position of the leg in a settlement
+//! - `fee`: part of `amount` credited to the fee collector, when charged
+//!
+//! A locked transfer with splits emits one event per recipient, the beneficiary
+//! first. Their `amount`s add up to the amount taken from the owner.
+
+use std::fmt;
+
+use cosmwasm_std::{Addr, Event};
+
+use crate::structs::CurrencyAccount;
+
+pub const BALANCE_CHANGE_EVENT: &str = "balance_change";
+
+pub const ACTION: &str = "action";
+pub const OWNER: &str = "owner";
+pub const CURRENCY: &str = "currency";
+pub const AMOUNT: &str = "amount";
+pub const AVAILABLE: &str = "available";
+pub const LOCKED: &str = "locked";
+pub const BENEFICIARY: &str = "beneficiary";
+pub const BENEFICIARY_AVAILABLE: &str = "beneficiary_available";
+pub const BENEFICIARY_LOCKED: &str = "beneficiary_locked";
+pub const DEPOSITOR: &str = "depositor";
+pub const HANDLER: &str = "handler";
+pub const LOCK_ID: &str = "lock_id";
+pub const LEG: &str = "leg";
+pub const FEE: &str = "fee";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BalanceAction {
+    Deposit,
+    Withdraw,
+    Transfer,
+    Lock,
+    Unlock,
+    TransferLocked,
+    ReclaimExpiredLock,
+}
+
+impl fmt::Display for BalanceAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let action = match self {
+            BalanceAction::Deposit => "deposit",
+            BalanceAction::Withdraw => "withdraw",
+            BalanceAction::Transfer => "transfer",
+            BalanceAction::Lock => "lock",
+            BalanceAction::Unlock => "unlock",
+            BalanceAction::TransferLocked => "transfer_locked",
+            BalanceAction::ReclaimExpiredLock => "reclaim_expired_lock",
+        };
+
+        return write!(f, "{}", action);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalanceChangeEvent {
+    pub action: BalanceAction,
+    pub owner: String,
+    pub currency_identifier: String,
+    pub amount: u128,
+    // balances of the owner after the change
+    pub balance: CurrencyAccount,
+    pub beneficiary: Option<String>,
+    // set when the beneficiary is credited inside the vault
+    pub beneficiary_balance: Option<CurrencyAccount>,
+    pub depositor: Option<String>,
+    pub handler: Option<Addr>,
+    pub lock_id: Option<u64>,
+    pub fee: Option<u128>,
+}
+
+impl BalanceChangeEvent {
+    pub fn new(
+        action: BalanceAction,
+        owner: String,
+        currency_identifier: String,
+        amount: u128,
+        balance: CurrencyAccount,
+    ) -> Self {
+        return BalanceChangeEvent {
+            action: action,
+            owner: owner,
+            currency_identifier: currency_identifier,
+            amount: amount,
+            balance: balance,
+            beneficiary: None,
+            beneficiary_balance: None,
+            depositor: None,
+            handler: None,
+            lock_id: None,
+            fee: None,
+        };
+    }
+
+    pub fn beneficiary(mut self, beneficiary: String, balance: Option<CurrencyAccount>) -> Self {
+        self.beneficiary = Some(beneficiary);
+        self.beneficiary_balance = balance;
+        return self;
+    }
+
+    pub fn depositor(mut self, depositor: String) -> Self {
+        self.depositor = Some(depositor);
+        return self;
+    }
+
+    pub fn handler(mut self, handler: Addr) -> Self {
+        self.handler = Some(handler);
+        return self;
+    }
+
+    pub fn lock_id(mut self, lock_id: Option<u64>) -> Self {
+        self.lock_id = lock_id;
+        return self;
+    }
+
+    pub fn fee(mut self, fee: u128) -> Self {
+        if fee > 0 {
+            self.fee = Some(fee);
+        }
+        return self;
+    }
+}
+
+impl From<BalanceChangeEvent> for Event {
+    fn from(change: BalanceChangeEvent) -> Self {
+        let mut event = Event::new(BALANCE_CHANGE_EVENT)
+            .add_attribute(ACTION, change.action.to_string())
+            .add_attribute(OWNER, change.owner)
+            .add_attribute(CURRENCY, change.currency_identifier)
+            .add_attribute(AMOUNT, change.amount.to_string())
+            .add_attribute(AVAILABLE, change.balance.available.to_string())
+            .add_attribute(LOCKED, change.balance.locked.to_string());
+
+        if let Some(beneficiary) = change.beneficiary {
+            event = event.add_attribute(BENEFICIARY, beneficiary);
+        }
+        if let Some(balance) = change.beneficiary_balance {
+            event = event
+                .add_attribute(BENEFICIARY_AVAILABLE, balance.available.to_string())
+                .add_attribute(BENEFICIARY_LOCKED, balance.locked.to_string());
+        }
+        if let Some(depositor) = change.depositor {
+            event = event.add_attribute(DEPOSITOR, depositor);
+        }
+        if let Some(handler) = change.handler {
+            event = event.add_attribute(HANDLER, handler);
+        }
+        if let Some(lock_id) = change.lock_id {
+            event = event.add_attribute(LOCK_ID, lock_id.to_string());
+        }
+        if let Some(fee) = change.fee {
+            event = event.add_attribute(FEE, fee.to_string());
+        }
+
+        return event;
+    }
+}
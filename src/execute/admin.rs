@@ -1,35 +1,212 @@
 use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
 
 use crate::error::ContractError;
+use crate::execute::default::payout_messages;
 use crate::execute_messages::msg_admin::AdminExecuteMsg;
 use crate::state::{state_reads, state_writes};
+use crate::structs::{Currency, CurrencyStatus, FeeSchedule, HandlerRecord, PauseStatus};
 
 pub fn dispatch_admin(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     admin_msg: AdminExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // called by the proposed admin, not the current one
+    if admin_msg == (AdminExecuteMsg::AcceptAdmin {}) {
+        return try_accept_admin(deps, info);
+    }
+
     if !state_reads::is_admin(deps.as_ref(), info.sender.clone())? {
         return Err(ContractError::Unauthorized {});
     }
 
     match admin_msg {
+        AdminExecuteMsg::ProposeNewAdmin { new_admin } => {
+            try_propose_new_admin(deps, info, new_admin)
+        }
+        AdminExecuteMsg::CancelAdminProposal {} => try_cancel_admin_proposal(deps),
+        AdminExecuteMsg::RenounceAdmin {} => try_renounce_admin(deps),
+        AdminExecuteMsg::SetGuardian { guardian } => try_set_guardian(deps, guardian),
+        AdminExecuteMsg::SetPauseStatus { status } => try_set_pause_status(deps, status),
         AdminExecuteMsg::SetAuthorizationStatus { target, new_status } => {
             try_set_authorization_status(deps, target, new_status)
         }
-        AdminExecuteMsg::AddValidCurrency { currency_id } => {
-            try_add_valid_currency(deps, currency_id)
+        AdminExecuteMsg::RegisterHandler { handler, record } => {
+            try_register_handler(deps, handler, record)
         }
+        AdminExecuteMsg::AddValidCurrency { currency } => try_add_valid_currency(deps, currency),
+        AdminExecuteMsg::RemoveValidCurrency { currency_id } => {
+            try_remove_valid_currency(deps, currency_id)
+        }
+        AdminExecuteMsg::SetCurrencyStatus {
+            currency_id,
+            status,
+        } => try_set_currency_status(deps, currency_id, status),
+        AdminExecuteMsg::SetFeeCollector { collector } => try_set_fee_collector(deps, collector),
+        AdminExecuteMsg::SetFeeSchedule {
+            currency_id,
+            schedule,
+        } => try_set_fee_schedule(deps, currency_id, schedule),
+        AdminExecuteMsg::SweepSurplus {
+            currency,
+            recipient,
+        } => try_sweep_surplus(deps, env, currency, recipient),
         //_ => return Ok(Response::new()),
         _ => Err(ContractError::Never {}),
     }
 }
 
-fn try_add_valid_currency(deps: DepsMut, currency_id: String) -> Result<Response, ContractError> {
-    state_writes::add_valid_currency(deps.storage, currency_id)?;
+fn try_propose_new_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_admin: String,
+) -> Result<Response, ContractError> {
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    if new_admin == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
 
-    return Ok(Response::new());
+    state_writes::admin::set_pending_admin(deps.storage, new_admin.clone())?;
+
+    return Ok(Response::new()
+        .add_attribute("action", "propose_new_admin")
+        .add_attribute("pending_admin", new_admin));
+}
+
+fn try_accept_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    match state_reads::get_pending_admin(deps.as_ref())? {
+        None => return Err(ContractError::NoPendingAdmin {}),
+        Some(pending_admin) => {
+            if pending_admin != info.sender {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
+    }
+
+    state_writes::admin::update_admin(deps.storage, info.sender.clone())?;
+
+    return Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("admin", info.sender));
+}
+
+fn try_cancel_admin_proposal(deps: DepsMut) -> Result<Response, ContractError> {
+    state_writes::admin::remove_pending_admin(deps.storage)?;
+
+    return Ok(Response::new().add_attribute("action", "cancel_admin_proposal"));
+}
+
+fn try_renounce_admin(deps: DepsMut) -> Result<Response, ContractError> {
+    state_writes::admin::remove_admin(deps.storage)?;
+
+    return Ok(Response::new().add_attribute("action", "renounce_admin"));
+}
+
+fn try_set_guardian(deps: DepsMut, guardian: Option<String>) -> Result<Response, ContractError> {
+    let guardian = match guardian {
+        Some(guardian) => Some(deps.api.addr_validate(&guardian)?),
+        None => None,
+    };
+    state_writes::admin::set_guardian(deps.storage, guardian)?;
+
+    return Ok(Response::new().add_attribute("action", "set_guardian"));
+}
+
+fn try_set_pause_status(deps: DepsMut, status: PauseStatus) -> Result<Response, ContractError> {
+    state_writes::admin::set_pause_status(deps.storage, status)?;
+
+    return Ok(Response::new().add_attribute("action", "set_pause_status"));
+}
+
+fn try_add_valid_currency(deps: DepsMut, currency: Currency) -> Result<Response, ContractError> {
+    let currency = currency.validate(deps.api)?;
+    let currency_id = currency.identifier();
+    state_writes::add_valid_currency(deps.storage, currency)?;
+
+    return Ok(Response::new()
+        .add_attribute("action", "add_valid_currency")
+        .add_attribute("currency", currency_id));
+}
+
+fn try_remove_valid_currency(
+    deps: DepsMut,
+    currency_id: String,
+) -> Result<Response, ContractError> {
+    state_writes::remove_valid_currency(deps.storage, currency_id.clone())?;
+
+    return Ok(Response::new()
+        .add_attribute("action", "remove_valid_currency")
+        .add_attribute("currency", currency_id));
+}
+
+fn try_set_currency_status(
+    deps: DepsMut,
+    currency_id: String,
+    status: CurrencyStatus,
+) -> Result<Response, ContractError> {
+    state_writes::set_currency_status(deps.storage, currency_id.clone(), status)?;
+
+    return Ok(Response::new()
+        .add_attribute("action", "set_currency_status")
+        .add_attribute("currency", currency_id));
+}
+
+fn try_set_fee_collector(
+    deps: DepsMut,
+    collector: Option<String>,
+) -> Result<Response, ContractError> {
+    let collector = match collector {
+        Some(collector) => Some(deps.api.addr_validate(&collector)?),
+        None => None,
+    };
+    state_writes::admin::set_fee_collector(deps.storage, collector)?;
+
+    return Ok(Response::new().add_attribute("action", "set_fee_collector"));
+}
+
+fn try_set_fee_schedule(
+    deps: DepsMut,
+    currency_id: String,
+    schedule: FeeSchedule,
+) -> Result<Response, ContractError> {
+    if !state_reads::is_valid_currency(deps.as_ref(), currency_id.clone())? {
+        return Err(ContractError::CurrencyNotAccepted {
+            currency_identifier: currency_id,
+        });
+    }
+    state_writes::admin::set_fee_schedule(deps.storage, currency_id.clone(), schedule)?;
+
+    return Ok(Response::new()
+        .add_attribute("action", "set_fee_schedule")
+        .add_attribute("currency", currency_id));
+}
+
+fn try_sweep_surplus(
+    deps: DepsMut,
+    env: Env,
+    currency_id: String,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let currency = state_reads::get_currency(deps.as_ref(), currency_id.clone())?;
+
+    // deposits include locked funds and the fee collector balance
+    let deposits = state_reads::get_total_deposits(deps.as_ref(), currency_id.clone())?;
+    let held = state_reads::get_contract_holdings(deps.as_ref(), &env.contract.address, &currency)?;
+    let surplus = held.saturating_sub(deposits.available + deposits.locked);
+    if surplus == 0 {
+        return Err(ContractError::NoSurplus {
+            currency_identifier: currency_id,
+        });
+    }
+
+    return Ok(Response::new()
+        .add_attribute("action", "sweep_surplus")
+        .add_attribute("currency", currency_id)
+        .add_attribute("amount", surplus.to_string())
+        .add_attribute("recipient", recipient.to_string())
+        .add_messages(payout_messages(&recipient, vec![(currency, surplus)])?));
 }
 
 fn try_set_authorization_status(
@@ -37,13 +214,26 @@ fn try_set_authorization_status(
     target: String,
     new_status: bool,
 ) -> Result<Response, ContractError> {
-    state_writes::admin::set_authorization_status(
-        deps.storage,
-        deps.api.addr_validate(&target)?,
-        new_status,
-    )?;
+    let handler = deps.api.addr_validate(&target)?;
+    state_writes::admin::set_authorization_status(deps.storage, handler.clone(), new_status)?;
 
-    return Ok(Response::new());
+    return Ok(Response::new()
+        .add_attribute("action", "set_authorization_status")
+        .add_attribute("handler", handler)
+        .add_attribute("authorized", new_status.to_string()));
+}
+
+fn try_register_handler(
+    deps: DepsMut,
+    handler: String,
+    record: HandlerRecord,
+) -> Result<Response, ContractError> {
+    let handler = deps.api.addr_validate(&handler)?;
+    state_writes::admin::register_handler(deps.storage, handler.clone(), record)?;
+
+    return Ok(Response::new()
+        .add_attribute("action", "register_handler")
+        .add_attribute("handler", handler));
 }
 
 fn _try_sample_execute(
@@ -1,13 +1,23 @@
 use std::str::FromStr;
 
 use cosmwasm_std::{
-    coin, from_binary, to_binary, BankMsg, Binary, CosmosMsg, DepsMut, Env, MessageInfo, Response,
-    Uint128, WasmMsg,
+    coin, from_binary, to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env,
+    Event, MessageInfo, Response, SubMsg, Uint128, Uint256, WasmMsg,
 };
 
+use cw_utils::Expiration;
+
 use crate::error::ContractError;
-use crate::execute_messages::msg::ExecuteMsg;
+use crate::events::{BalanceAction, BalanceChangeEvent, LEG};
+use crate::execute_messages::msg::{
+    Asset, DepositLock, DepositNotification, ExecuteMsg, ReceiveMsg, SettlementLeg, Split,
+    SplitShare, TransferItem, WithdrawItem,
+};
+use crate::hooks::SafeDepositHookMsg;
 use crate::state::{state_reads, state_writes};
+use crate::structs::{
+    AssetInfo, Currency, CurrencyOperation, HandlerAllowance, HandlerOperation, PauseStatus,
+};
 
 pub fn dispatch_default(
     deps: DepsMut,
@@ -15,240 +25,1193 @@ pub fn dispatch_default(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    if is_paused(&state_reads::get_pause_status(deps.as_ref())?, &msg) {
+        return Err(ContractError::Paused {});
+    }
+
     match msg {
         ExecuteMsg::Receive {
             sender,
             amount,
             msg,
-        } => try_receive(deps, info, sender, amount, msg),
-        ExecuteMsg::DepositNative { beneficiary } => try_deposit_native(deps, info, beneficiary),
+        } => try_receive(deps, env, info, sender, amount, msg),
+        ExecuteMsg::DepositNative {
+            beneficiary,
+            notify,
+            lock,
+        } => try_deposit_native(deps, env, info, beneficiary, notify, lock),
         ExecuteMsg::DepositCw20 {
             sender,
             beneficiary,
             token_address,
             amount,
-        } => try_deposit_cw20(deps, env, sender, beneficiary, token_address, amount),
+            notify,
+            lock,
+        } => try_deposit_cw20(
+            deps,
+            env,
+            info,
+            sender,
+            beneficiary,
+            token_address,
+            amount,
+            notify,
+            lock,
+        ),
+        ExecuteMsg::Transfer {
+            recipient,
+            currency_identifier,
+            amount,
+        } => try_transfer(deps, info, recipient, currency_identifier, amount),
+        ExecuteMsg::BatchTransfer { transfers } => try_batch_transfer(deps, info, transfers),
+        ExecuteMsg::ApproveHandler {
+            handler,
+            currency_identifier,
+            max_amount,
+            expires,
+        } => try_approve_handler(
+            deps,
+            env,
+            info,
+            handler,
+            currency_identifier,
+            max_amount,
+            expires,
+        ),
+        ExecuteMsg::RevokeHandler {
+            handler,
+            currency_identifier,
+        } => try_revoke_handler(deps, info, handler, currency_identifier),
         ExecuteMsg::Lock {
             account,
             currency_identifier,
             amount,
-        } => try_lock(deps, info, account, currency_identifier, amount),
+            expires,
+        } => try_lock(
+            deps,
+            env,
+            info,
+            account,
+            currency_identifier,
+            amount,
+            expires,
+        ),
         ExecuteMsg::Unlock {
             account,
             currency_identifier,
             amount,
-        } => try_unlock(deps, info, account, currency_identifier, amount),
+            lock_id,
+        } => try_unlock(
+            deps,
+            env,
+            info,
+            account,
+            currency_identifier,
+            amount,
+            lock_id,
+        ),
         ExecuteMsg::TransferLocked {
             account,
             currency_identifier,
             amount,
             beneficiary,
+            lock_id,
+            splits,
         } => try_transfer_lock(
             deps,
+            env,
             info,
             account,
             currency_identifier,
             amount,
             beneficiary,
+            lock_id,
+            splits,
+        ),
+        ExecuteMsg::Withdraw { asset, beneficiary } => try_withdraw(deps, info, asset, beneficiary),
+        ExecuteMsg::Settle { legs } => try_settle(deps, env, info, legs),
+        ExecuteMsg::TransferLockedAndWithdraw {
+            account,
+            currency_identifier,
+            amount,
+            beneficiary,
+            lock_id,
+        } => try_transfer_locked_and_withdraw(
+            deps,
+            env,
+            info,
+            account,
+            currency_identifier,
+            amount,
+            beneficiary,
+            lock_id,
         ),
         ExecuteMsg::WithdrawNative {
             beneficiary,
             denom,
             amount,
-        } => try_withdraw_native(deps, info, beneficiary, denom, amount),
+        } => try_withdraw(
+            deps,
+            info,
+            Asset {
+                info: AssetInfo::Native { denom: denom },
+                amount: amount,
+            },
+            beneficiary,
+        ),
         ExecuteMsg::WithdrawCw20 {
             beneficiary,
             token_address,
             amount,
-        } => try_withdraw_cw20(deps, info, beneficiary, token_address, amount),
+        } => try_withdraw(
+            deps,
+            info,
+            Asset {
+                info: AssetInfo::Cw20 {
+                    token_address: token_address,
+                },
+                amount: amount,
+            },
+            beneficiary,
+        ),
+        ExecuteMsg::WithdrawMany {
+            beneficiary,
+            withdrawals,
+        } => try_withdraw_many(deps, info, beneficiary, withdrawals),
+        ExecuteMsg::WithdrawAll { beneficiary } => try_withdraw_all(deps, info, beneficiary),
+        ExecuteMsg::SetHandlerHook { hook } => try_set_handler_hook(deps, info, hook),
+        ExecuteMsg::EmergencyPause {} => try_emergency_pause(deps, info),
+        ExecuteMsg::ReclaimExpiredLock { lock_id } => {
+            try_reclaim_expired_lock(deps, env, info, lock_id)
+        }
         _ => Err(ContractError::Never {}),
     }
 }
 
-fn try_withdraw_cw20(
+fn is_paused(status: &PauseStatus, msg: &ExecuteMsg) -> bool {
+    match msg {
+        ExecuteMsg::Receive { .. }
+        | ExecuteMsg::DepositNative { .. }
+        | ExecuteMsg::DepositCw20 { .. } => return status.deposits,
+        ExecuteMsg::Withdraw { .. }
+        | ExecuteMsg::WithdrawNative { .. }
+        | ExecuteMsg::WithdrawCw20 { .. }
+        | ExecuteMsg::WithdrawMany { .. }
+        | ExecuteMsg::WithdrawAll { .. } => return status.withdrawals,
+        ExecuteMsg::Transfer { .. } | ExecuteMsg::BatchTransfer { .. } => return status.transfers,
+        ExecuteMsg::Lock { .. }
+        | ExecuteMsg::Unlock { .. }
+        | ExecuteMsg::ReclaimExpiredLock { .. } => return status.locks,
+        ExecuteMsg::TransferLocked { .. } => return status.locked_transfers,
+        // legs can unlock and transfer locked funds
+        ExecuteMsg::Settle { .. } => return status.locks || status.locked_transfers,
+        ExecuteMsg::TransferLockedAndWithdraw { .. } => {
+            return status.locked_transfers || status.withdrawals
+        }
+        _ => return false,
+    }
+}
+
+fn try_set_handler_hook(
     deps: DepsMut,
     info: MessageInfo,
-    beneficiary: String,
-    token_address: String,
-    amount: String,
+    hook: Option<String>,
 ) -> Result<Response, ContractError> {
-    if !state_reads::is_valid_currency(deps.as_ref(), token_address.clone())? {
-        return Err(ContractError::Cw20NotAccepted {
-            token_address: token_address,
-        });
+    if !state_reads::is_authorized_handler(deps.as_ref(), info.sender.clone())? {
+        return Err(ContractError::Unauthorized {});
     }
 
-    let mut account = state_reads::get_currency_account(
+    let hook = match hook {
+        Some(hook) => Some(deps.api.addr_validate(&hook)?),
+        None => None,
+    };
+    state_writes::set_handler_hook(deps.storage, info.sender, hook)?;
+
+    return Ok(Response::new().add_attribute("action", "set_handler_hook"));
+}
+
+fn try_emergency_pause(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    if !state_reads::is_guardian(deps.as_ref(), info.sender.clone())?
+        && !state_reads::is_admin(deps.as_ref(), info.sender)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state_writes::admin::set_pause_status(deps.storage, PauseStatus::all())?;
+
+    return Ok(Response::new().add_attribute("action", "emergency_pause"));
+}
+
+fn try_withdraw(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset: Asset,
+    beneficiary: String,
+) -> Result<Response, ContractError> {
+    let beneficiary = deps.api.addr_validate(&beneficiary)?;
+
+    let currency = match state_reads::get_currency(deps.as_ref(), asset.info.identifier()) {
+        Ok(currency) if currency.asset_info() == asset.info => currency,
+        _ => return Err(asset_not_accepted(asset.info)),
+    };
+    state_reads::check_currency_operation(
         deps.as_ref(),
-        info.sender.into_string(),
-        token_address.clone(),
+        currency.identifier(),
+        CurrencyOperation::Withdraw,
     )?;
-    let amount_num = Uint128::from_str(&amount)?;
 
-    if amount_num.u128() > account.available {
-        return Err(ContractError::InsufficientFundsAvailableForCw20Withdrawal {
-            currency_identifier: token_address,
-            available: account.available.to_string(),
-            required: amount,
-        });
+    let amount = Uint128::from_str(&asset.amount)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
     }
 
-    account.available -= amount_num.u128();
-    state_writes::update_currency_account(
-        deps.storage,
-        beneficiary.clone(),
-        token_address.clone(),
-        account,
-    )?;
+    return withdraw_currencies(
+        deps,
+        info.sender.into_string(),
+        beneficiary,
+        vec![(currency, amount.u128())],
+        "withdraw",
+    );
+}
 
-    let msg = cw20::Cw20ExecuteMsg::Transfer {
-        recipient: beneficiary,
-        amount: amount_num,
-    };
-    let wasm_msg = WasmMsg::Execute {
-        contract_addr: token_address,
-        msg: to_binary(&msg)?,
-        funds: vec![],
-    };
-    let cosmos_msg = CosmosMsg::Wasm(wasm_msg);
+fn asset_not_accepted(asset: AssetInfo) -> ContractError {
+    match asset {
+        AssetInfo::Native { denom } => {
+            return ContractError::NativeCurrencyNotAccepted { denom: denom }
+        }
+        AssetInfo::Cw20 { token_address } => {
+            return ContractError::Cw20NotAccepted {
+                token_address: token_address,
+            }
+        }
+    }
+}
+
+fn try_withdraw_many(
+    deps: DepsMut,
+    info: MessageInfo,
+    beneficiary: String,
+    withdrawals: Vec<WithdrawItem>,
+) -> Result<Response, ContractError> {
+    let beneficiary = deps.api.addr_validate(&beneficiary)?;
 
-    return Ok(Response::new().add_message(cosmos_msg));
+    let mut currency_withdrawals = vec![];
+    for withdrawal in withdrawals {
+        let currency = state_reads::get_currency(deps.as_ref(), withdrawal.currency_identifier)?;
+        state_reads::check_currency_operation(
+            deps.as_ref(),
+            currency.identifier(),
+            CurrencyOperation::Withdraw,
+        )?;
+
+        let amount = Uint128::from_str(&withdrawal.amount)?;
+        if amount.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        currency_withdrawals.push((currency, amount.u128()));
+    }
+
+    return withdraw_currencies(
+        deps,
+        info.sender.into_string(),
+        beneficiary,
+        currency_withdrawals,
+        "withdraw_many",
+    );
 }
 
-fn try_withdraw_native(
+fn try_withdraw_all(
     deps: DepsMut,
     info: MessageInfo,
     beneficiary: String,
-    denom: String,
-    amount: String,
 ) -> Result<Response, ContractError> {
-    if !state_reads::is_valid_currency(deps.as_ref(), denom.clone())? {
-        return Err(ContractError::NativeCurrencyNotAccepted { denom: denom });
+    let beneficiary = deps.api.addr_validate(&beneficiary)?;
+    let owner = info.sender.into_string();
+
+    let mut currency_withdrawals = vec![];
+    for currency in state_reads::get_currencies(deps.as_ref(), None, usize::MAX)? {
+        let currency_identifier = currency.identifier();
+        let status = state_reads::get_currency_status(deps.as_ref(), currency_identifier.clone())?;
+        if !status.allows(&CurrencyOperation::Withdraw) {
+            continue;
+        }
+
+        let account = state_reads::get_currency_account(
+            deps.as_ref(),
+            owner.clone(),
+            currency_identifier.clone(),
+        )?;
+        if account.available == 0 {
+            continue;
+        }
+
+        // balances not covering the withdraw fee stay in the vault
+        let fee = match state_reads::compute_fee(
+            deps.as_ref(),
+            owner.clone(),
+            currency_identifier,
+            CurrencyOperation::Withdraw,
+            account.available,
+        ) {
+            Ok(fee) => fee,
+            Err(ContractError::FeeExceedsAmount { .. }) => continue,
+            Err(err) => return Err(err),
+        };
+        if fee < account.available {
+            currency_withdrawals.push((currency, account.available));
+        }
+    }
+
+    return withdraw_currencies(
+        deps,
+        owner,
+        beneficiary,
+        currency_withdrawals,
+        "withdraw_all",
+    );
+}
+
+// debits the owner for each currency, native coins are merged in a single bank message
+fn withdraw_currencies(
+    deps: DepsMut,
+    owner: String,
+    beneficiary: Addr,
+    withdrawals: Vec<(Currency, u128)>,
+    action: &str,
+) -> Result<Response, ContractError> {
+    let mut response = Response::new().add_attribute("action", action);
+    let mut payouts = vec![];
+
+    for (currency, amount) in withdrawals {
+        let currency_identifier = currency.identifier();
+        let mut account = state_reads::get_currency_account(
+            deps.as_ref(),
+            owner.clone(),
+            currency_identifier.clone(),
+        )?;
+
+        if amount > account.available {
+            return Err(match currency {
+                Currency::Native { .. } => {
+                    ContractError::InsufficientFundsAvailableForNativeWithdrawal {
+                        currency_identifier: currency_identifier,
+                        available: account.available.to_string(),
+                        required: amount.to_string(),
+                    }
+                }
+                Currency::Cw20 { .. } => {
+                    ContractError::InsufficientFundsAvailableForCw20Withdrawal {
+                        currency_identifier: currency_identifier,
+                        available: account.available.to_string(),
+                        required: amount.to_string(),
+                    }
+                }
+            });
+        }
+
+        let fee = state_reads::compute_fee(
+            deps.as_ref(),
+            owner.clone(),
+            currency_identifier.clone(),
+            CurrencyOperation::Withdraw,
+            amount,
+        )?;
+        account.available -= amount;
+        state_writes::update_currency_account(
+            deps.storage,
+            owner.clone(),
+            currency_identifier.clone(),
+            account.clone(),
+        )?;
+        state_writes::collect_fee(deps.storage, currency_identifier.clone(), fee)?;
+
+        let event = BalanceChangeEvent::new(
+            BalanceAction::Withdraw,
+            owner.clone(),
+            currency_identifier,
+            amount,
+            account,
+        )
+        .beneficiary(beneficiary.to_string(), None)
+        .fee(fee);
+        response = response.add_event(event.into());
+        payouts.push((currency, amount - fee));
     }
 
-    let mut account =
-        state_reads::get_currency_account(deps.as_ref(), info.sender.into_string(), denom.clone())?;
-    let amount_num = Uint128::from_str(&amount)?.u128();
+    return Ok(response.add_messages(payout_messages(&beneficiary, payouts)?));
+}
 
-    if amount_num > account.available {
-        return Err(
-            ContractError::InsufficientFundsAvailableForNativeWithdrawal {
-                currency_identifier: denom,
-                available: account.available.to_string(),
-                required: amount,
-            },
-        );
+// native coins are merged in a single bank message, cw20 tokens get one transfer each,
+// amounts fully taken by fees are skipped
+pub fn payout_messages(
+    beneficiary: &Addr,
+    payouts: Vec<(Currency, u128)>,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut messages = vec![];
+    let mut coins: Vec<Coin> = vec![];
+
+    for (currency, amount) in payouts {
+        if amount == 0 {
+            continue;
+        }
+        match currency {
+            Currency::Native { denom, .. } => {
+                match coins.iter_mut().find(|coin| coin.denom == denom) {
+                    Some(coin) => coin.amount += Uint128::from(amount),
+                    None => coins.push(coin(amount, denom)),
+                }
+            }
+            Currency::Cw20 { token_address, .. } => {
+                let msg = cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: beneficiary.to_string(),
+                    amount: Uint128::from(amount),
+                };
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: token_address,
+                    msg: to_binary(&msg)?,
+                    funds: vec![],
+                }));
+            }
+        }
     }
 
-    account.available -= amount_num;
-    state_writes::update_currency_account(
-        deps.storage,
-        beneficiary.clone(),
-        denom.clone(),
+    if !coins.is_empty() {
+        coins.sort_by(|a, b| a.denom.cmp(&b.denom));
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: beneficiary.to_string(),
+            amount: coins,
+        }));
+    }
+
+    return Ok(messages);
+}
+
+fn try_transfer_lock(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    account: String,
+    currency_identifier: String,
+    amount: String,
+    beneficiary: String,
+    lock_id: Option<u64>,
+    splits: Option<Vec<Split>>,
+) -> Result<Response, ContractError> {
+    let events = transfer_locked(
+        deps,
+        &env,
+        info.sender,
         account,
+        currency_identifier,
+        amount,
+        beneficiary,
+        lock_id,
+        splits.unwrap_or_default(),
     )?;
 
-    let bank_msg = BankMsg::Send {
-        to_address: beneficiary,
-        amount: vec![coin(amount_num, denom.clone())],
-    };
-    let transfer_msg = CosmosMsg::Bank(bank_msg);
+    return Ok(Response::new()
+        .add_attribute("action", "transfer_locked")
+        .add_events(events.into_iter().map(Event::from)));
+}
 
-    return Ok(Response::new().add_message(transfer_msg));
+fn transfer_locked(
+    deps: DepsMut,
+    env: &Env,
+    handler: Addr,
+    account: String,
+    currency_identifier: String,
+    amount: String,
+    beneficiary: String,
+    lock_id: Option<u64>,
+    splits: Vec<Split>,
+) -> Result<Vec<BalanceChangeEvent>, ContractError> {
+    state_reads::check_currency_operation(
+        deps.as_ref(),
+        currency_identifier.clone(),
+        CurrencyOperation::TransferLocked,
+    )?;
+
+    let record = state_reads::check_handler_operation(
+        deps.as_ref(),
+        handler.clone(),
+        HandlerOperation::TransferLocked,
+        Some(currency_identifier.clone()),
+    )?;
+
+    let amount_num = Uint128::from_str(&amount)?.u128();
+    if amount_num == 0 {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    state_writes::use_handler_cap(
+        deps.storage,
+        &env.block,
+        handler.clone(),
+        &record,
+        currency_identifier.clone(),
+        amount_num,
+    )?;
+    let fee = state_reads::compute_fee(
+        deps.as_ref(),
+        account.clone(),
+        currency_identifier.clone(),
+        CurrencyOperation::TransferLocked,
+        amount_num,
+    )?;
+    let credits = split_credits(
+        deps.as_ref(),
+        currency_identifier.clone(),
+        amount_num - fee,
+        beneficiary,
+        splits,
+    )?;
+    state_writes::transfer_locked_value(
+        deps.storage,
+        &env.block,
+        account.clone(),
+        currency_identifier.clone(),
+        handler.clone(),
+        lock_id,
+        amount_num,
+        credits.clone(),
+        fee,
+    )?;
+
+    let mut events = vec![];
+    for (index, (recipient, credited)) in credits.into_iter().enumerate() {
+        let recipient_balance = state_reads::get_currency_account(
+            deps.as_ref(),
+            recipient.clone(),
+            currency_identifier.clone(),
+        )?;
+        // the beneficiary comes first and carries the fee
+        let event_fee = if index == 0 { fee } else { 0 };
+        let event = balance_change(
+            deps.as_ref(),
+            BalanceAction::TransferLocked,
+            account.clone(),
+            currency_identifier.clone(),
+            credited + event_fee,
+        )?
+        .beneficiary(recipient, Some(recipient_balance))
+        .handler(handler.clone())
+        .lock_id(lock_id)
+        .fee(event_fee);
+        events.push(event);
+    }
+
+    return Ok(events);
 }
 
-fn try_transfer_lock(
+// beneficiary first with the remainder of the net amount, then each split recipient
+fn split_credits(
+    deps: Deps,
+    currency_identifier: String,
+    net_amount: u128,
+    beneficiary: String,
+    splits: Vec<Split>,
+) -> Result<Vec<(String, u128)>, ContractError> {
+    let beneficiary = validate_transfer_recipient(deps, beneficiary)?;
+
+    let mut credits = vec![];
+    let mut split_total: u128 = 0;
+
+    for split in splits {
+        let recipient = validate_transfer_recipient(deps, split.recipient)?;
+        let amount = match split.share {
+            SplitShare::Amount { amount } => Uint128::from_str(&amount)?.u128(),
+            SplitShare::Bps { bps } => {
+                if bps > 10000 {
+                    return Err(ContractError::InvalidSplit {});
+                }
+                Uint128::from(net_amount)
+                    .multiply_ratio(bps, 10000u64)
+                    .u128()
+            }
+        };
+        if amount == 0 {
+            return Err(ContractError::InvalidSplit {});
+        }
+
+        split_total = match split_total.checked_add(amount) {
+            Some(split_total) => split_total,
+            None => {
+                return Err(ContractError::SplitExceedsAmount {
+                    currency_identifier: currency_identifier,
+                    amount: net_amount.to_string(),
+                    split_total: (Uint256::from(split_total) + Uint256::from(amount)).to_string(),
+                })
+            }
+        };
+        credits.push((recipient.into_string(), amount));
+    }
+
+    if split_total > net_amount {
+        return Err(ContractError::SplitExceedsAmount {
+            currency_identifier: currency_identifier,
+            amount: net_amount.to_string(),
+            split_total: split_total.to_string(),
+        });
+    }
+
+    credits.insert(0, (beneficiary.into_string(), net_amount - split_total));
+
+    return Ok(credits);
+}
+
+fn validate_transfer_recipient(deps: Deps, recipient: String) -> Result<Addr, ContractError> {
+    match deps.api.addr_validate(&recipient) {
+        Ok(addr) => return Ok(addr),
+        Err(_) => {
+            return Err(ContractError::InvalidTransferRecipient {
+                recipient: recipient,
+            })
+        }
+    }
+}
+
+fn try_transfer_locked_and_withdraw(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     account: String,
     currency_identifier: String,
     amount: String,
     beneficiary: String,
+    lock_id: Option<u64>,
 ) -> Result<Response, ContractError> {
-    if !state_reads::is_authorized_handler(deps.as_ref(), info.sender)? {
-        return Err(ContractError::Unauthorized {});
-    }
+    let record = state_reads::check_handler_operation(
+        deps.as_ref(),
+        info.sender.clone(),
+        HandlerOperation::TransferLocked,
+        Some(currency_identifier.clone()),
+    )?;
+    let beneficiary = deps.api.addr_validate(&beneficiary)?;
 
-    state_writes::transfer_locked_value(
+    let currency = state_reads::get_currency(deps.as_ref(), currency_identifier.clone())?;
+    state_reads::check_currency_operation(
+        deps.as_ref(),
+        currency_identifier.clone(),
+        CurrencyOperation::TransferLocked,
+    )?;
+    state_reads::check_currency_operation(
+        deps.as_ref(),
+        currency_identifier.clone(),
+        CurrencyOperation::Withdraw,
+    )?;
+
+    let amount_num = Uint128::from_str(&amount)?.u128();
+    if amount_num == 0 {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    state_writes::use_handler_cap(
+        deps.storage,
+        &env.block,
+        info.sender.clone(),
+        &record,
+        currency_identifier.clone(),
+        amount_num,
+    )?;
+    let fee = state_reads::compute_fee(
+        deps.as_ref(),
+        account.clone(),
+        currency_identifier.clone(),
+        CurrencyOperation::TransferLocked,
+        amount_num,
+    )?;
+    state_writes::debit_locked_value(
         deps.storage,
+        &env.block,
+        account.clone(),
+        currency_identifier.clone(),
+        info.sender.clone(),
+        lock_id,
+        amount_num,
+    )?;
+    state_writes::collect_fee(deps.storage, currency_identifier.clone(), fee)?;
+
+    let event = balance_change(
+        deps.as_ref(),
+        BalanceAction::TransferLocked,
         account,
-        beneficiary,
         currency_identifier,
-        Uint128::from_str(&amount)?.u128(),
-    )?;
+        amount_num,
+    )?
+    .beneficiary(beneficiary.to_string(), None)
+    .handler(info.sender)
+    .lock_id(lock_id)
+    .fee(fee);
 
-    return Ok(Response::new());
+    return Ok(Response::new()
+        .add_attribute("action", "transfer_locked_and_withdraw")
+        .add_event(event.into())
+        .add_messages(payout_messages(
+            &beneficiary,
+            vec![(currency, amount_num - fee)],
+        )?));
 }
 
 fn try_lock(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     account: String,
     currency_identifier: String,
     amount: String,
+    expires: Option<Expiration>,
 ) -> Result<Response, ContractError> {
-    if !state_reads::is_authorized_handler(deps.as_ref(), info.sender)? {
-        return Err(ContractError::Unauthorized {});
+    state_reads::check_handler_operation(
+        deps.as_ref(),
+        info.sender.clone(),
+        HandlerOperation::Lock,
+        Some(currency_identifier.clone()),
+    )?;
+    state_reads::check_currency_operation(
+        deps.as_ref(),
+        currency_identifier.clone(),
+        CurrencyOperation::Lock,
+    )?;
+
+    let amount_num = Uint128::from_str(&amount)?.u128();
+    if amount_num == 0 {
+        return Err(ContractError::InvalidZeroAmount {});
     }
+    state_writes::use_handler_allowance(
+        deps.storage,
+        &env.block,
+        account.clone(),
+        currency_identifier.clone(),
+        info.sender.clone(),
+        amount_num,
+    )?;
 
-    state_writes::increase_locked_value(
+    let lock_id = state_writes::increase_locked_value(
         deps.storage,
+        &env.block,
+        account.clone(),
+        currency_identifier.clone(),
+        info.sender.clone(),
+        amount_num,
+        expires.unwrap_or_default(),
+    )?;
+
+    let event = balance_change(
+        deps.as_ref(),
+        BalanceAction::Lock,
         account,
         currency_identifier,
-        Uint128::from_str(&amount)?.u128(),
-    )
-    .unwrap();
+        amount_num,
+    )?
+    .handler(info.sender)
+    .lock_id(Some(lock_id));
 
-    return Ok(Response::new());
+    return Ok(Response::new()
+        .add_attribute("action", "lock")
+        .add_attribute("lock_id", lock_id.to_string())
+        .add_event(event.into()));
 }
 
 fn try_unlock(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     account: String,
     currency_identifier: String,
     amount: String,
+    lock_id: Option<u64>,
 ) -> Result<Response, ContractError> {
-    if !state_reads::is_authorized_handler(deps.as_ref(), info.sender)? {
-        return Err(ContractError::Unauthorized {});
-    }
+    let (event, hook) = unlock(
+        deps,
+        &env,
+        info.sender,
+        account,
+        currency_identifier,
+        amount,
+        lock_id,
+    )?;
+
+    return Ok(Response::new()
+        .add_attribute("action", "unlock")
+        .add_event(event.into())
+        .add_submessages(hook));
+}
+
+fn unlock(
+    deps: DepsMut,
+    env: &Env,
+    handler: Addr,
+    account: String,
+    currency_identifier: String,
+    amount: String,
+    lock_id: Option<u64>,
+) -> Result<(BalanceChangeEvent, Option<SubMsg>), ContractError> {
+    state_reads::check_handler_operation(
+        deps.as_ref(),
+        handler.clone(),
+        HandlerOperation::Unlock,
+        Some(currency_identifier.clone()),
+    )?;
+    state_reads::check_currency_operation(
+        deps.as_ref(),
+        currency_identifier.clone(),
+        CurrencyOperation::Unlock,
+    )?;
 
+    let amount_num = Uint128::from_str(&amount)?.u128();
+    if amount_num == 0 {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
     state_writes::decrease_locked_value(
         deps.storage,
+        &env.block,
+        account.clone(),
+        currency_identifier.clone(),
+        handler.clone(),
+        lock_id,
+        amount_num,
+    )?;
+
+    let hook = handler_hook(
+        deps.as_ref(),
+        handler.clone(),
+        SafeDepositHookMsg::LockReleased {
+            owner: account.clone(),
+            currency_identifier: currency_identifier.clone(),
+            amount: Uint128::from(amount_num),
+            lock_id: lock_id,
+        },
+    )?;
+    let event = balance_change(
+        deps.as_ref(),
+        BalanceAction::Unlock,
         account,
         currency_identifier,
-        Uint128::from_str(&amount)?.u128(),
-    )
-    .unwrap();
+        amount_num,
+    )?
+    .handler(handler)
+    .lock_id(lock_id);
 
-    return Ok(Response::new());
+    return Ok((event, hook));
 }
 
-fn try_deposit_cw20(
+fn try_settle(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    legs: Vec<SettlementLeg>,
+) -> Result<Response, ContractError> {
+    // legs are also limited by the operation they perform
+    state_reads::check_handler_operation(
+        deps.as_ref(),
+        info.sender.clone(),
+        HandlerOperation::Settle,
+        None,
+    )?;
+
+    // any failing leg reverts the whole settlement
+    let mut response = Response::new().add_attribute("action", "settle");
+    for (index, leg) in legs.into_iter().enumerate() {
+        let events = match leg {
+            SettlementLeg::TransferLocked {
+                account,
+                currency_identifier,
+                amount,
+                beneficiary,
+                lock_id,
+                splits,
+            } => transfer_locked(
+                deps.branch(),
+                &env,
+                info.sender.clone(),
+                account,
+                currency_identifier,
+                amount,
+                beneficiary,
+                lock_id,
+                splits.unwrap_or_default(),
+            )?,
+            SettlementLeg::Unlock {
+                account,
+                currency_identifier,
+                amount,
+                lock_id,
+            } => {
+                let (event, hook) = unlock(
+                    deps.branch(),
+                    &env,
+                    info.sender.clone(),
+                    account,
+                    currency_identifier,
+                    amount,
+                    lock_id,
+                )?;
+                response = response.add_submessages(hook);
+                vec![event]
+            }
+        };
+
+        for event in events {
+            response = response.add_event(Event::from(event).add_attribute(LEG, index.to_string()));
+        }
+    }
+
+    return Ok(response);
+}
+
+fn try_transfer(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient: String,
+    currency_identifier: String,
+    amount: String,
+) -> Result<Response, ContractError> {
+    let event = transfer_available(
+        deps,
+        &info,
+        TransferItem {
+            recipient: recipient,
+            currency_identifier: currency_identifier,
+            amount: amount,
+        },
+    )?;
+
+    return Ok(Response::new()
+        .add_attribute("action", "transfer")
+        .add_event(event.into()));
+}
+
+fn try_batch_transfer(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    transfers: Vec<TransferItem>,
+) -> Result<Response, ContractError> {
+    let mut response = Response::new().add_attribute("action", "batch_transfer");
+    for transfer in transfers {
+        response = response.add_event(transfer_available(deps.branch(), &info, transfer)?.into());
+    }
+
+    return Ok(response);
+}
+
+fn transfer_available(
+    deps: DepsMut,
+    info: &MessageInfo,
+    transfer: TransferItem,
+) -> Result<BalanceChangeEvent, ContractError> {
+    let recipient = validate_transfer_recipient(deps.as_ref(), transfer.recipient)?;
+
+    if recipient == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+
+    if !state_reads::is_valid_currency(deps.as_ref(), transfer.currency_identifier.clone())? {
+        return Err(ContractError::CurrencyNotAccepted {
+            currency_identifier: transfer.currency_identifier,
+        });
+    }
+    state_reads::check_currency_operation(
+        deps.as_ref(),
+        transfer.currency_identifier.clone(),
+        CurrencyOperation::Transfer,
+    )?;
+
+    let amount = Uint128::from_str(&transfer.amount)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    state_writes::transfer_available_value(
+        deps.storage,
+        info.sender.to_string(),
+        recipient.to_string(),
+        transfer.currency_identifier.clone(),
+        amount.u128(),
+    )?;
+
+    let recipient_balance = state_reads::get_currency_account(
+        deps.as_ref(),
+        recipient.to_string(),
+        transfer.currency_identifier.clone(),
+    )?;
+    let event = balance_change(
+        deps.as_ref(),
+        BalanceAction::Transfer,
+        info.sender.to_string(),
+        transfer.currency_identifier,
+        amount.u128(),
+    )?
+    .beneficiary(recipient.into_string(), Some(recipient_balance));
+
+    return Ok(event);
+}
+
+fn try_approve_handler(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    handler: String,
+    currency_identifier: String,
+    max_amount: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let handler = deps.api.addr_validate(&handler)?;
+    if handler == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+
+    if !state_reads::is_valid_currency(deps.as_ref(), currency_identifier.clone())? {
+        return Err(ContractError::CurrencyNotAccepted {
+            currency_identifier: currency_identifier,
+        });
+    }
+
+    let max_amount = Uint128::from_str(&max_amount)?;
+    if max_amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+
+    state_writes::set_handler_allowance(
+        deps.storage,
+        info.sender.into_string(),
+        currency_identifier,
+        handler,
+        HandlerAllowance {
+            remaining: max_amount.u128(),
+            expires: expires,
+        },
+    )?;
+
+    return Ok(Response::new().add_attribute("action", "approve_handler"));
+}
+
+fn try_revoke_handler(
+    deps: DepsMut,
+    info: MessageInfo,
+    handler: String,
+    currency_identifier: String,
+) -> Result<Response, ContractError> {
+    state_writes::remove_handler_allowance(
+        deps.storage,
+        info.sender.into_string(),
+        currency_identifier,
+        deps.api.addr_validate(&handler)?,
+    )?;
+
+    return Ok(Response::new().add_attribute("action", "revoke_handler"));
+}
+
+fn try_reclaim_expired_lock(
     deps: DepsMut,
     env: Env,
+    info: MessageInfo,
+    lock_id: u64,
+) -> Result<Response, ContractError> {
+    let lock = state_reads::get_lock(deps.as_ref(), lock_id)?;
+    state_reads::check_currency_operation(
+        deps.as_ref(),
+        lock.currency_identifier.clone(),
+        CurrencyOperation::Unlock,
+    )?;
+    let handler_can_release =
+        state_reads::can_release_locks(deps.as_ref(), lock.handler, lock.currency_identifier)?;
+
+    let lock = state_writes::reclaim_expired_lock(
+        deps.storage,
+        &env.block,
+        info.sender.into_string(),
+        lock_id,
+        handler_can_release,
+    )?;
+
+    let hook = handler_hook(
+        deps.as_ref(),
+        lock.handler.clone(),
+        SafeDepositHookMsg::LockReleased {
+            owner: lock.owner.clone(),
+            currency_identifier: lock.currency_identifier.clone(),
+            amount: Uint128::from(lock.amount),
+            lock_id: Some(lock_id),
+        },
+    )?;
+    let event = balance_change(
+        deps.as_ref(),
+        BalanceAction::ReclaimExpiredLock,
+        lock.owner,
+        lock.currency_identifier,
+        lock.amount,
+    )?
+    .handler(lock.handler)
+    .lock_id(Some(lock_id));
+
+    return Ok(Response::new()
+        .add_attribute("action", "reclaim_expired_lock")
+        .add_attribute("lock_id", lock_id.to_string())
+        .add_attribute("amount", lock.amount.to_string())
+        .add_event(event.into())
+        .add_submessages(hook));
+}
+
+fn try_deposit_cw20(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
     sender: String,
     beneficiary: String,
     token_address: String,
     amount: String,
+    notify: Option<DepositNotification>,
+    lock: Option<DepositLock>,
 ) -> Result<Response, ContractError> {
-    if state_reads::is_valid_currency(deps.as_ref(), token_address.clone())? {
+    // only the owner of the tokens can deposit from the allowance it gave to the vault
+    if info.sender != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // validate that beneficiary is a valid address
+    match deps.api.addr_validate(beneficiary.as_str()) {
+        Ok(_) => (),
+        Err(_) => {
+            return Err(ContractError::InvalidDepositBeneficiary {
+                beneficiary: beneficiary,
+            })
+        }
+    }
+
+    let amount = Uint128::from_str(&amount)?;
+    let event = if state_reads::is_valid_cw20_currency(deps.as_ref(), token_address.clone())? {
+        state_reads::check_currency_operation(
+            deps.as_ref(),
+            token_address.clone(),
+            CurrencyOperation::Deposit,
+        )?;
         state_writes::update_deposit(
             deps.storage,
             beneficiary.clone(),
             token_address.clone(),
-            Uint128::from_str(&amount).unwrap().u128(),
+            amount.u128(),
         )?;
+        balance_change(
+            deps.as_ref(),
+            BalanceAction::Deposit,
+            beneficiary,
+            token_address.clone(),
+            amount.u128(),
+        )?
+        .depositor(sender.clone())
     } else {
         return Err(ContractError::Cw20NotAccepted {
             token_address: token_address.clone(),
         });
-    }
+    };
 
     let cw_msg = cw20::Cw20ExecuteMsg::TransferFrom {
         owner: sender,
-        recipient: env.contract.address.into_string(),
-        amount: Uint128::from_str(&amount)?,
+        recipient: env.contract.address.to_string(),
+        amount: amount,
     };
     let msg = WasmMsg::Execute {
         contract_addr: token_address,
@@ -256,38 +1219,116 @@ fn try_deposit_cw20(
         funds: vec![],
     };
 
-    return Ok(Response::new().add_message(msg));
+    let hook = deposit_received_hook(
+        deps.as_ref(),
+        notify,
+        event.owner.clone(),
+        event.currency_identifier.clone(),
+        event.amount,
+    )?;
+
+    let response = Response::new()
+        .add_message(msg)
+        .add_attribute("action", "deposit")
+        .add_event(event.clone().into())
+        .add_submessages(hook);
+
+    return lock_deposit(
+        deps.branch(),
+        &env,
+        response,
+        event.owner,
+        event.currency_identifier,
+        event.amount,
+        lock,
+    );
 }
 
 fn try_receive(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    _sender: String,
+    sender: String,
     amount: Uint128,
     msg: Binary,
 ) -> Result<Response, ContractError> {
-    let beneficiary: String = from_binary(&msg)?;
+    let (beneficiary, lock, notify) = match from_binary(&msg)? {
+        ReceiveMsg::Deposit { beneficiary } => (beneficiary, None, None),
+        ReceiveMsg::DepositAndLock {
+            beneficiary,
+            lock,
+            notify,
+        } => (beneficiary, Some(lock), notify),
+        ReceiveMsg::DepositFor {
+            beneficiary,
+            notify,
+        } => (Some(beneficiary), None, notify),
+    };
+
+    let beneficiary = beneficiary.unwrap_or_else(|| sender.clone());
+    if deps.api.addr_validate(&beneficiary).is_err() {
+        return Err(ContractError::InvalidDepositBeneficiary {
+            beneficiary: beneficiary,
+        });
+    }
 
-    if state_reads::is_valid_currency(deps.as_ref(), info.sender.to_string())? {
+    let event = if state_reads::is_valid_cw20_currency(deps.as_ref(), info.sender.to_string())? {
+        state_reads::check_currency_operation(
+            deps.as_ref(),
+            info.sender.to_string(),
+            CurrencyOperation::Deposit,
+        )?;
         state_writes::update_deposit(
             deps.storage,
             beneficiary.clone(),
             info.sender.to_string(),
             amount.u128(),
         )?;
+        balance_change(
+            deps.as_ref(),
+            BalanceAction::Deposit,
+            beneficiary,
+            info.sender.to_string(),
+            amount.u128(),
+        )?
+        .depositor(sender)
     } else {
         return Err(ContractError::Cw20NotAccepted {
             token_address: info.sender.to_string(),
         });
-    }
+    };
 
-    return Ok(Response::new());
+    let hook = deposit_received_hook(
+        deps.as_ref(),
+        notify,
+        event.owner.clone(),
+        event.currency_identifier.clone(),
+        event.amount,
+    )?;
+
+    let response = Response::new()
+        .add_attribute("action", "deposit")
+        .add_event(event.clone().into())
+        .add_submessages(hook);
+
+    return lock_deposit(
+        deps,
+        &env,
+        response,
+        event.owner,
+        event.currency_identifier,
+        event.amount,
+        lock,
+    );
 }
 
 fn try_deposit_native(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     beneficiary: String,
+    notify: Option<DepositNotification>,
+    lock: Option<DepositLock>,
 ) -> Result<Response, ContractError> {
     //     return Ok(Response::new());
 
@@ -301,13 +1342,51 @@ fn try_deposit_native(
         }
     }
 
+    // the lock amount applies to the only deposited coin
+    if lock.is_some() && info.funds.len() != 1 {
+        return Err(ContractError::SingleCurrencyPayable {});
+    }
+
+    let mut response = Response::new().add_attribute("action", "deposit");
     for deposit in info.funds {
-        if state_reads::is_valid_currency(deps.as_ref(), deposit.denom.clone())? {
+        if state_reads::is_valid_native_currency(deps.as_ref(), deposit.denom.clone())? {
+            state_reads::check_currency_operation(
+                deps.as_ref(),
+                deposit.denom.clone(),
+                CurrencyOperation::Deposit,
+            )?;
             state_writes::update_deposit(
                 deps.storage,
                 beneficiary.clone(),
+                deposit.denom.clone(),
+                deposit.amount.u128(),
+            )?;
+            response = response.add_submessages(deposit_received_hook(
+                deps.as_ref(),
+                notify.clone(),
+                beneficiary.clone(),
+                deposit.denom.clone(),
+                deposit.amount.u128(),
+            )?);
+            response = response.add_event(
+                balance_change(
+                    deps.as_ref(),
+                    BalanceAction::Deposit,
+                    beneficiary.clone(),
+                    deposit.denom.clone(),
+                    deposit.amount.u128(),
+                )?
+                .depositor(info.sender.to_string())
+                .into(),
+            );
+            response = lock_deposit(
+                deps.branch(),
+                &env,
+                response,
+                beneficiary.clone(),
                 deposit.denom,
                 deposit.amount.u128(),
+                lock.clone(),
             )?;
         } else {
             return Err(ContractError::NativeCurrencyNotAccepted {
@@ -316,7 +1395,144 @@ fn try_deposit_native(
         }
     }
 
-    return Ok(Response::new());
+    return Ok(response);
+}
+
+// reports the balance of the owner once the change is stored
+fn balance_change(
+    deps: Deps,
+    action: BalanceAction,
+    owner: String,
+    currency_identifier: String,
+    amount: u128,
+) -> Result<BalanceChangeEvent, ContractError> {
+    let balance =
+        state_reads::get_currency_account(deps, owner.clone(), currency_identifier.clone())?;
+
+    return Ok(BalanceChangeEvent::new(
+        action,
+        owner,
+        currency_identifier,
+        amount,
+        balance,
+    ));
+}
+
+fn deposit_received_hook(
+    deps: Deps,
+    notify: Option<DepositNotification>,
+    owner: String,
+    currency_identifier: String,
+    amount: u128,
+) -> Result<Option<SubMsg>, ContractError> {
+    let notify = match notify {
+        Some(notify) => notify,
+        None => return Ok(None),
+    };
+    let contract = deps.api.addr_validate(&notify.contract)?;
+
+    let hook = SafeDepositHookMsg::DepositReceived {
+        owner: owner,
+        currency_identifier: currency_identifier,
+        amount: Uint128::from(amount),
+        payload: notify.payload,
+    };
+
+    return Ok(Some(hook.into_sub_msg(contract.into_string())?));
+}
+
+// notifies the hook registered by the handler, if any
+fn handler_hook(
+    deps: Deps,
+    handler: Addr,
+    hook: SafeDepositHookMsg,
+) -> Result<Option<SubMsg>, ContractError> {
+    let contract = match state_reads::get_handler_hook(deps, handler)? {
+        Some(contract) => contract,
+        None => return Ok(None),
+    };
+
+    return Ok(Some(hook.into_sub_msg(contract.into_string())?));
+}
+
+// the depositor consents to the lock, so only deposited funds can be locked this way
+fn lock_deposit(
+    deps: DepsMut,
+    env: &Env,
+    response: Response,
+    beneficiary: String,
+    currency_identifier: String,
+    deposited: u128,
+    lock: Option<DepositLock>,
+) -> Result<Response, ContractError> {
+    let lock = match lock {
+        Some(lock) => lock,
+        None => return Ok(response),
+    };
+
+    if state_reads::get_pause_status(deps.as_ref())?.locks {
+        return Err(ContractError::Paused {});
+    }
+
+    let handler = deps.api.addr_validate(&lock.handler)?;
+    state_reads::check_handler_operation(
+        deps.as_ref(),
+        handler.clone(),
+        HandlerOperation::Lock,
+        Some(currency_identifier.clone()),
+    )?;
+    state_reads::check_currency_operation(
+        deps.as_ref(),
+        currency_identifier.clone(),
+        CurrencyOperation::Lock,
+    )?;
+
+    let amount = Uint128::from_str(&lock.amount)?.u128();
+    if amount == 0 {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    if amount > deposited {
+        return Err(ContractError::InsufficientFundsAvailableForLock {
+            currency_identifier: currency_identifier,
+            available: deposited.to_string(),
+            required: lock.amount,
+        });
+    }
+
+    let lock_id = state_writes::increase_locked_value(
+        deps.storage,
+        &env.block,
+        beneficiary.clone(),
+        currency_identifier.clone(),
+        handler.clone(),
+        amount,
+        lock.expires.unwrap_or_default(),
+    )?;
+
+    let hook = handler_hook(
+        deps.as_ref(),
+        handler.clone(),
+        SafeDepositHookMsg::DepositLocked {
+            owner: beneficiary.clone(),
+            currency_identifier: currency_identifier.clone(),
+            amount: Uint128::from(amount),
+            lock_id: lock_id,
+        },
+    )?;
+    let event = balance_change(
+        deps.as_ref(),
+        BalanceAction::Lock,
+        beneficiary,
+        currency_identifier,
+        amount,
+    )?
+    .handler(handler)
+    .lock_id(Some(lock_id));
+
+    return Ok(response
+        .add_attribute("lock_id", lock_id.to_string())
+        .add_event(event.into())
+        .add_submessages(hook));
 }
 
 fn _try_sample_execute(
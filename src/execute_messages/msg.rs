@@ -1,11 +1,103 @@
 use cosmwasm_std::{Binary, Uint128};
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::execute_messages::msg_admin::AdminExecuteMsg;
+use crate::structs::{AssetInfo, Currency};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    // kind and metadata of the currencies accepted before they were stored, keyed by identifier
+    pub legacy_currencies: Option<Vec<Currency>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TransferItem {
+    pub recipient: String,
+    pub currency_identifier: String,
+    pub amount: String,
+}
+
+// part of a locked transfer credited to another recipient than the beneficiary
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Split {
+    pub recipient: String,
+    pub share: SplitShare,
+}
+
+// shares are taken from the transferred amount net of protocol fees
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitShare {
+    Amount { amount: String },
+    Bps { bps: u64 },
+}
+
+// operation on locked funds of the handler, applied in order by a settlement
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementLeg {
+    TransferLocked {
+        account: String,
+        currency_identifier: String,
+        amount: String,
+        beneficiary: String,
+        lock_id: Option<u64>,
+        splits: Option<Vec<Split>>,
+    },
+    Unlock {
+        account: String,
+        currency_identifier: String,
+        amount: String,
+        lock_id: Option<u64>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Asset {
+    pub info: AssetInfo,
+    pub amount: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct WithdrawItem {
+    pub currency_identifier: String,
+    pub amount: String,
+}
+
+// contract receiving a DepositReceived hook, with an optional payload forwarded as is
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DepositNotification {
+    pub contract: String,
+    pub payload: Option<Binary>,
+}
+
+// deposited funds locked right away for an authorized handler, in place of the owner allowance
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DepositLock {
+    pub handler: String,
+    pub amount: String,
+    pub expires: Option<Expiration>,
+}
+
+// message of a cw20 Send to the vault, the cw20 sender is the beneficiary unless another one is given
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    Deposit {
+        beneficiary: Option<String>,
+    },
+    DepositAndLock {
+        beneficiary: Option<String>,
+        lock: DepositLock,
+        notify: Option<DepositNotification>,
+    },
+    DepositFor {
+        beneficiary: String,
+        notify: Option<DepositNotification>,
+    },
+}
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
@@ -20,17 +112,28 @@ pub enum ExecuteMsg {
     },
 
     // User deposits. Caller can deposit for someone else
+    // with lock, part of the deposit is locked for the handler in the same call
     DepositNative {
         beneficiary: String,
+        notify: Option<DepositNotification>,
+        lock: Option<DepositLock>,
     },
+    // sender must be the caller, which gave the vault an allowance on the token
     DepositCw20 {
         sender: String,
         beneficiary: String,
         token_address: String,
         amount: String,
+        notify: Option<DepositNotification>,
+        lock: Option<DepositLock>,
     },
 
     // user withdraws. Can only withdraw from own account, but can transfer to anybody
+    Withdraw {
+        asset: Asset,
+        beneficiary: String,
+    },
+    // aliases of Withdraw for a native or cw20 asset
     WithdrawNative {
         beneficiary: String,
         denom: String,
@@ -41,23 +144,87 @@ pub enum ExecuteMsg {
         token_address: String,
         amount: String,
     },
+    // native coins are sent in a single bank message, cw20 tokens with one transfer each
+    WithdrawMany {
+        beneficiary: String,
+        withdrawals: Vec<WithdrawItem>,
+    },
+    // every available balance, skipping currencies that cannot be withdrawn
+    WithdrawAll {
+        beneficiary: String,
+    },
+
+    // user moves available funds to other depositors, without leaving the vault
+    Transfer {
+        recipient: String,
+        currency_identifier: String,
+        amount: String,
+    },
+    BatchTransfer {
+        transfers: Vec<TransferItem>,
+    },
+
+    // Users allow services to lock their deposits, up to max_amount in total over all locks
+    ApproveHandler {
+        handler: String,
+        currency_identifier: String,
+        max_amount: String,
+        expires: Option<Expiration>,
+    },
+    RevokeHandler {
+        handler: String,
+        currency_identifier: String,
+    },
 
     // Services can lock / unlock deposits
+    // a lock returns its identifier in the lock_id attribute
+    // once expired, a lock can no longer be used by the service and the owner can reclaim it
     Lock {
         account: String,
         currency_identifier: String,
         amount: String,
+        expires: Option<Expiration>,
     },
+    // without lock_id, the service's locks on the account are consumed from the oldest one
     Unlock {
         account: String,
         currency_identifier: String,
         amount: String,
+        lock_id: Option<u64>,
     },
-    // and transfer locked deposits
+    // and transfer locked deposits, the beneficiary receives what is left after the splits
     TransferLocked {
         account: String,
         currency_identifier: String,
         amount: String,
         beneficiary: String,
+        lock_id: Option<u64>,
+        splits: Option<Vec<Split>>,
+    },
+
+    // Services register a contract notified when their locks are released, None to stop notifications
+    SetHandlerHook {
+        hook: Option<String>,
+    },
+
+    // several lock transfers and unlocks, applied atomically
+    Settle {
+        legs: Vec<SettlementLeg>,
+    },
+    // and pay locked deposits out of the vault
+    TransferLockedAndWithdraw {
+        account: String,
+        currency_identifier: String,
+        amount: String,
+        beneficiary: String,
+        lock_id: Option<u64>,
+    },
+
+    // guardian or admin stops every operation at once
+    EmergencyPause {},
+
+    // owner takes back the funds of an expired lock, or of a lock its handler can no longer release
+    ReclaimExpiredLock {
+        lock_id: u64,
     },
 }
@@ -1,9 +1,63 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::structs::{Currency, CurrencyStatus, FeeSchedule, HandlerRecord, PauseStatus};
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum AdminExecuteMsg {
-    SetAuthorizationStatus { target: String, new_status: bool },
-    AddValidCurrency { currency_id: String },
+    // admin handover: proposed admin must accept the role before it is transferred
+    ProposeNewAdmin {
+        new_admin: String,
+    },
+    AcceptAdmin {},
+    CancelAdminProposal {},
+    // leaves the contract without admin, for good
+    RenounceAdmin {},
+
+    // authorizing registers the handler without limits, unless already registered
+    SetAuthorizationStatus {
+        target: String,
+        new_status: bool,
+    },
+    // registers a handler, or replaces the record of a registered one
+    RegisterHandler {
+        handler: String,
+        record: HandlerRecord,
+    },
+    // registers a currency, or updates the metadata of an already registered one
+    AddValidCurrency {
+        currency: Currency,
+    },
+    // guardian can trigger the emergency pause, only the admin lifts it
+    SetGuardian {
+        guardian: Option<String>,
+    },
+    SetPauseStatus {
+        status: PauseStatus,
+    },
+
+    // only currencies no longer held by any account can be removed
+    RemoveValidCurrency {
+        currency_id: String,
+    },
+    SetCurrencyStatus {
+        currency_id: String,
+        status: CurrencyStatus,
+    },
+
+    // fees are only charged while a collector is set
+    SetFeeCollector {
+        collector: Option<String>,
+    },
+    SetFeeSchedule {
+        currency_id: String,
+        schedule: FeeSchedule,
+    },
+
+    // sends funds held by the contract beyond total deposits, e.g. sent without a deposit message
+    SweepSurplus {
+        currency: String,
+        recipient: String,
+    },
 }
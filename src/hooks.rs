@@ -0,0 +1,72 @@
+//! Notifications sent to other contracts when their funds change in the vault.
+//!
+//! Receivers get a `{"safe_deposit_hook": <SafeDepositHookMsg>}` execute message.
+//! Hooks are dispatched as submessages replying on error only, with a bounded gas
+//! limit: a failing receiver, or one running out of gas, has its own changes
+//! reverted but never fails the operation that notified it.
+
+use cosmwasm_std::{to_binary, Binary, Reply, Response, StdResult, SubMsg, Uint128, WasmMsg};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+
+pub const HOOK_REPLY_ID: u64 = 1;
+// out of gas errors are only caught by the reply when the submessage has its own limit
+pub const HOOK_GAS_LIMIT: u64 = 200_000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SafeDepositHookMsg {
+    // funds credited to owner, payload is forwarded from the deposit message
+    DepositReceived {
+        owner: String,
+        currency_identifier: String,
+        amount: Uint128,
+        payload: Option<Binary>,
+    },
+    // deposit locked for the handler in the same call
+    DepositLocked {
+        owner: String,
+        currency_identifier: String,
+        amount: Uint128,
+        lock_id: u64,
+    },
+    // locked funds of the handler returned to the owner available balance
+    LockReleased {
+        owner: String,
+        currency_identifier: String,
+        amount: Uint128,
+        lock_id: Option<u64>,
+    },
+}
+
+// wraps the hook so receivers can add it as a variant of their own ExecuteMsg
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum ReceiverExecuteMsg {
+    SafeDepositHook(SafeDepositHookMsg),
+}
+
+impl SafeDepositHookMsg {
+    pub fn into_sub_msg(self, contract_addr: String) -> StdResult<SubMsg> {
+        let msg = WasmMsg::Execute {
+            contract_addr: contract_addr,
+            msg: to_binary(&ReceiverExecuteMsg::SafeDepositHook(self))?,
+            funds: vec![],
+        };
+
+        return Ok(SubMsg::reply_on_error(msg, HOOK_REPLY_ID).with_gas_limit(HOOK_GAS_LIMIT));
+    }
+}
+
+pub fn handle_hook_reply(msg: Reply) -> Result<Response, ContractError> {
+    match msg.result.into_result() {
+        Ok(_) => return Ok(Response::new()),
+        Err(err) => {
+            return Ok(Response::new()
+                .add_attribute("action", "hook_failed")
+                .add_attribute("error", err))
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
 
 use crate::{
-    state::state_entries::{ADMIN, VALID_CURRENCIES},
+    state::{state_entries::ADMIN, state_writes},
     ContractError,
 };
 
@@ -11,11 +11,29 @@ pub fn execute_instantiation(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    _msg: InstantiateMsg,
+    msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    ADMIN.save(deps.storage, &info.sender)?;
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender,
+    };
+    ADMIN.save(deps.storage, &admin)?;
 
-    VALID_CURRENCIES.save(deps.storage, &vec![])?;
+    let mut response = Response::default()
+        .add_attribute("action", "instantiate")
+        .add_attribute("admin", admin);
 
-    return Ok(Response::default());
+    for currency in msg.currencies {
+        let currency = currency.validate(deps.api)?;
+        response = response.add_attribute("currency", currency.identifier());
+        state_writes::add_valid_currency(deps.storage, currency)?;
+    }
+
+    for handler in msg.handlers {
+        let handler = deps.api.addr_validate(&handler)?;
+        response = response.add_attribute("handler", handler.clone());
+        state_writes::admin::set_authorization_status(deps.storage, handler, true)?;
+    }
+
+    return Ok(response);
 }
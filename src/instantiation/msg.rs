@@ -1,6 +1,16 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+use crate::structs::Currency;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
 #[serde(rename_all = "snake_case")]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    // defaults to the instantiator
+    pub admin: Option<String>,
+    #[serde(default)]
+    pub currencies: Vec<Currency>,
+    // authorized handlers
+    #[serde(default)]
+    pub handlers: Vec<String>,
+}
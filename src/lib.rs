@@ -1,8 +1,12 @@
 #![allow(unreachable_patterns)]
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+#![allow(clippy::new_without_default, clippy::too_many_arguments)]
 
 pub mod contract;
 mod contract_tests;
 mod error;
+pub mod events;
+pub mod hooks;
 
 pub mod execute_messages;
 pub mod instantiation;
@@ -1,27 +1,350 @@
 use cosmwasm_std::{Deps, Env, StdResult};
 
 use crate::state::state_reads;
+use crate::structs::{Currency, LockRecord};
 
-use super::query_response::{AllCurrencyAccountResponse, CurrencyAccountResponse};
+use super::query_response::{
+    AdminResponse, AllCurrencyAccountResponse, ConfigResponse, CurrenciesResponse,
+    CurrencyAccountResponse, CurrencyBalance, CurrencyHoldersResponse, CurrencyResponse,
+    FeesResponse, HandlerAllowanceInfo, HandlerAllowancesResponse, HandlerLockedBalance,
+    HandlerResponse, HandlersResponse, HolderBalance, LockResponse, LockedBalancesResponse,
+    LocksResponse, PauseStatusResponse, SolvencyResponse, TotalsResponse,
+};
+
+// pagination
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
 
 pub fn sample_query(_deps: Deps, _env: Env) -> StdResult<bool> {
     return Ok(true);
 }
 
+pub fn get_admin(deps: Deps) -> StdResult<AdminResponse> {
+    let admin = state_reads::get_admin(deps)?;
+    let pending_admin = state_reads::get_pending_admin(deps)?;
+
+    return Ok(AdminResponse {
+        admin: admin.map(|addr| addr.into_string()),
+        pending_admin: pending_admin.map(|addr| addr.into_string()),
+    });
+}
+
+pub fn get_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let admin = state_reads::get_admin(deps)?;
+    let currencies = state_reads::get_currencies(deps, None, usize::MAX)?
+        .into_iter()
+        .map(|currency| to_currency_response(deps, currency))
+        .collect::<StdResult<Vec<CurrencyResponse>>>()?;
+    let handlers = state_reads::get_authorized_handlers(deps)?;
+
+    return Ok(ConfigResponse {
+        admin: admin.map(|addr| addr.into_string()),
+        currencies: currencies,
+        handlers: handlers
+            .into_iter()
+            .map(|handler| handler.into_string())
+            .collect(),
+    });
+}
+
+pub fn get_handler(deps: Deps, handler: String) -> StdResult<HandlerResponse> {
+    let handler = deps.api.addr_validate(&handler)?;
+    let record = state_reads::get_handler(deps, handler.clone())?;
+
+    return Ok(HandlerResponse {
+        handler: handler.into_string(),
+        record: record,
+    });
+}
+
+pub fn list_handlers(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<HandlersResponse> {
+    let start_after = match start_after {
+        Some(handler) => Some(deps.api.addr_validate(&handler)?),
+        None => None,
+    };
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let handlers = state_reads::get_handlers(deps, start_after, limit)?
+        .into_iter()
+        .map(|(handler, record)| HandlerResponse {
+            handler: handler.into_string(),
+            record: record,
+        })
+        .collect();
+
+    return Ok(HandlersResponse { handlers: handlers });
+}
+
+pub fn get_pause_status(deps: Deps) -> StdResult<PauseStatusResponse> {
+    let status = state_reads::get_pause_status(deps)?;
+    let guardian = state_reads::get_guardian(deps)?;
+
+    return Ok(PauseStatusResponse {
+        status: status,
+        guardian: guardian.map(|addr| addr.into_string()),
+    });
+}
+
+pub fn get_fees(deps: Deps, currency_id: String) -> StdResult<FeesResponse> {
+    state_reads::get_currency(deps, currency_id.clone())?;
+    let collector = state_reads::get_fee_collector(deps)?;
+    let schedule = state_reads::get_fee_schedule(deps, currency_id.clone())?;
+    let accrued = state_reads::get_accrued_fees(deps, currency_id)?;
+
+    return Ok(FeesResponse {
+        collector: collector.map(|addr| addr.into_string()),
+        schedule: schedule,
+        accrued: accrued,
+    });
+}
+
 pub fn get_currency_account(
     deps: Deps,
     account_owner: String,
     currency_id: String,
-) -> CurrencyAccountResponse {
-    let account_data = state_reads::get_currency_account(deps, account_owner, currency_id).unwrap();
+) -> StdResult<CurrencyAccountResponse> {
+    let account_owner = deps.api.addr_validate(&account_owner)?;
+    // unknown currencies are an error, accepted currencies never held are a zero balance
+    state_reads::get_currency(deps, currency_id.clone())?;
+    let account_data =
+        state_reads::get_currency_account(deps, account_owner.into_string(), currency_id)?;
 
-    return CurrencyAccountResponse {
+    return Ok(CurrencyAccountResponse {
         account: account_data,
+    });
+}
+
+pub fn get_all_currency_accounts(
+    deps: Deps,
+    account_owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    hide_zero: bool,
+) -> StdResult<AllCurrencyAccountResponse> {
+    let account_owner = deps.api.addr_validate(&account_owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let accounts = state_reads::get_all_currency_accounts(
+        deps,
+        account_owner.into_string(),
+        start_after,
+        limit,
+        hide_zero,
+    )?
+    .into_iter()
+    .map(|(currency_id, account)| CurrencyBalance {
+        currency_id: currency_id,
+        account: account,
+    })
+    .collect();
+
+    return Ok(AllCurrencyAccountResponse { accounts: accounts });
+}
+
+pub fn get_totals(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TotalsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let totals = state_reads::get_all_total_deposits(deps, start_after, limit)?
+        .into_iter()
+        .map(|(currency_id, account)| CurrencyBalance {
+            currency_id: currency_id,
+            account: account,
+        })
+        .collect();
+
+    return Ok(TotalsResponse { totals: totals });
+}
+
+pub fn get_solvency(deps: Deps, env: Env, currency_id: String) -> StdResult<SolvencyResponse> {
+    let currency = state_reads::get_currency(deps, currency_id.clone())?;
+    let deposits = state_reads::get_total_deposits(deps, currency_id.clone())?;
+    let held = state_reads::get_contract_holdings(deps, &env.contract.address, &currency)?;
+
+    return Ok(SolvencyResponse {
+        currency_id: currency_id,
+        solvent: held >= deposits.available + deposits.locked,
+        deposits: deposits,
+        held: held,
+    });
+}
+
+pub fn list_accounts_for_currency(
+    deps: Deps,
+    currency: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CurrencyHoldersResponse> {
+    state_reads::get_currency(deps, currency.clone())?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let accounts = state_reads::get_currency_holders(deps, currency, start_after, limit)?
+        .into_iter()
+        .map(|(owner, account)| HolderBalance {
+            owner: owner,
+            account: account,
+        })
+        .collect();
+
+    return Ok(CurrencyHoldersResponse { accounts: accounts });
+}
+
+pub fn get_currency(deps: Deps, currency_id: String) -> StdResult<CurrencyResponse> {
+    let currency = state_reads::get_currency(deps, currency_id)?;
+
+    return to_currency_response(deps, currency);
+}
+
+pub fn list_currencies(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CurrenciesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let currencies = state_reads::get_currencies(deps, start_after, limit)?
+        .into_iter()
+        .map(|currency| to_currency_response(deps, currency))
+        .collect::<StdResult<Vec<CurrencyResponse>>>()?;
+
+    return Ok(CurrenciesResponse {
+        currencies: currencies,
+    });
+}
+
+fn to_currency_response(deps: Deps, currency: Currency) -> StdResult<CurrencyResponse> {
+    let status = state_reads::get_currency_status(deps, currency.identifier())?;
+
+    return Ok(CurrencyResponse {
+        currency: currency,
+        status: status,
+    });
+}
+
+pub fn get_locked_balances(
+    deps: Deps,
+    account_owner: String,
+    currency_id: String,
+) -> StdResult<LockedBalancesResponse> {
+    let account_owner = deps.api.addr_validate(&account_owner)?;
+    state_reads::get_currency(deps, currency_id.clone())?;
+    let locks = state_reads::get_handler_locked_values(
+        deps,
+        account_owner.into_string(),
+        currency_id.clone(),
+    )?
+    .into_iter()
+    .map(|(handler, locked)| HandlerLockedBalance {
+        currency_id: currency_id.clone(),
+        handler: handler.into_string(),
+        locked: locked,
+    })
+    .collect();
+
+    return Ok(LockedBalancesResponse { locks: locks });
+}
+
+pub fn get_all_locked_balances(
+    deps: Deps,
+    account_owner: String,
+) -> StdResult<LockedBalancesResponse> {
+    let account_owner = deps.api.addr_validate(&account_owner)?;
+    let locks = state_reads::get_all_handler_locked_values(deps, account_owner.into_string())?
+        .into_iter()
+        .map(|(currency_id, handler, locked)| HandlerLockedBalance {
+            currency_id: currency_id,
+            handler: handler.into_string(),
+            locked: locked,
+        })
+        .collect();
+
+    return Ok(LockedBalancesResponse { locks: locks });
+}
+
+pub fn get_lock(deps: Deps, lock_id: u64) -> StdResult<LockResponse> {
+    let lock = state_reads::get_lock(deps, lock_id)?;
+
+    return Ok(LockResponse {
+        lock_id: lock_id,
+        lock: lock,
+    });
+}
+
+pub fn list_locks_by_owner(
+    deps: Deps,
+    account_owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<LocksResponse> {
+    let account_owner = deps.api.addr_validate(&account_owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let locks =
+        state_reads::get_locks_by_owner(deps, account_owner.into_string(), start_after, limit)?;
+
+    return Ok(to_locks_response(locks));
+}
+
+pub fn list_locks_by_handler(
+    deps: Deps,
+    handler: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<LocksResponse> {
+    let handler = deps.api.addr_validate(&handler)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let locks = state_reads::get_locks_by_handler(deps, handler, start_after, limit)?;
+
+    return Ok(to_locks_response(locks));
+}
+
+fn to_locks_response(locks: Vec<(u64, LockRecord)>) -> LocksResponse {
+    return LocksResponse {
+        locks: locks
+            .into_iter()
+            .map(|(lock_id, lock)| LockResponse {
+                lock_id: lock_id,
+                lock: lock,
+            })
+            .collect(),
     };
 }
 
-pub fn get_all_currency_accounts(deps: Deps, account_owner: String) -> AllCurrencyAccountResponse {
-    let accounts = state_reads::get_all_currency_accounts(deps, account_owner).unwrap();
+pub fn list_handler_allowances(
+    deps: Deps,
+    account_owner: String,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<HandlerAllowancesResponse> {
+    let account_owner = deps.api.addr_validate(&account_owner)?.into_string();
+    let start_after = match start_after {
+        Some((currency_id, handler)) => Some((currency_id, deps.api.addr_validate(&handler)?)),
+        None => None,
+    };
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let locked_balances = get_all_locked_balances(deps, account_owner.clone())?.locks;
+    let allowances = state_reads::get_handler_allowances(deps, account_owner, start_after, limit)?
+        .into_iter()
+        .map(|(currency_id, handler, allowance)| {
+            let locked = locked_balances
+                .iter()
+                .find(|lock| lock.currency_id == currency_id && lock.handler == handler)
+                .map(|lock| lock.locked)
+                .unwrap_or_default();
+
+            HandlerAllowanceInfo {
+                currency_id: currency_id,
+                handler: handler.into_string(),
+                remaining: allowance.remaining,
+                locked: locked,
+                expires: allowance.expires,
+            }
+        })
+        .collect();
 
-    return AllCurrencyAccountResponse { accounts: accounts };
+    return Ok(HandlerAllowancesResponse {
+        allowances: allowances,
+    });
 }
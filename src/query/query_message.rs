@@ -4,11 +4,82 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
+    Admin {},
+    // admin, accepted currencies and authorized handlers
+    Config {},
+    PauseStatus {},
     GetBalance {
         account_owner: String,
         currency_id: String,
     },
+    // balances of the owner per accepted currency, ordered by currency identifier
     GetAllBalances {
         account_owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        hide_zero: Option<bool>,
+    },
+    // accounts holding a non-zero balance of the currency, ordered by owner
+    ListAccountsForCurrency {
+        currency: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // accepted currencies with their metadata
+    GetCurrency {
+        currency_id: String,
+    },
+    ListCurrencies {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // locked value of an account, broken down per handler
+    GetLockedBalances {
+        account_owner: String,
+        currency_id: String,
+    },
+    GetAllLockedBalances {
+        account_owner: String,
+    },
+    // allowances given by an account owner to handlers, start_after is a (currency_id, handler) pair
+    ListHandlerAllowances {
+        account_owner: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    // lock records
+    GetLock {
+        lock_id: u64,
+    },
+    ListLocksByOwner {
+        account_owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    ListLocksByHandler {
+        handler: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // sum of all balances per currency, ordered by currency identifier
+    Totals {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // compares the total deposits of a currency with the amount held by the contract
+    SolvencyCheck {
+        currency_id: String,
+    },
+    // registered handlers with their permissions, ordered by address
+    GetHandler {
+        handler: String,
+    },
+    ListHandlers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // fee schedule of a currency and total fees collected in it
+    Fees {
+        currency_id: String,
     },
 }
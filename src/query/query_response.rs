@@ -1,11 +1,52 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::structs::CurrencyAccount;
+use cw_utils::Expiration;
+
+use crate::structs::{
+    Currency, CurrencyAccount, CurrencyStatus, FeeSchedule, HandlerRecord, LockRecord, PauseStatus,
+};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct SampleResponse {}
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AdminResponse {
+    pub admin: Option<String>,
+    pub pending_admin: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ConfigResponse {
+    pub admin: Option<String>,
+    pub currencies: Vec<CurrencyResponse>,
+    pub handlers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HandlerResponse {
+    pub handler: String,
+    pub record: HandlerRecord,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HandlersResponse {
+    pub handlers: Vec<HandlerResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PauseStatusResponse {
+    pub status: PauseStatus,
+    pub guardian: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct FeesResponse {
+    pub collector: Option<String>,
+    pub schedule: FeeSchedule,
+    pub accrued: u128,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct CurrencyAccountResponse {
     pub account: CurrencyAccount,
@@ -13,5 +54,84 @@ pub struct CurrencyAccountResponse {
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct AllCurrencyAccountResponse {
-    pub accounts: Vec<CurrencyAccount>,
+    pub accounts: Vec<CurrencyBalance>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CurrencyBalance {
+    pub currency_id: String,
+    pub account: CurrencyAccount,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TotalsResponse {
+    pub totals: Vec<CurrencyBalance>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SolvencyResponse {
+    pub currency_id: String,
+    pub deposits: CurrencyAccount,
+    pub held: u128,
+    // held covers available plus locked deposits
+    pub solvent: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CurrencyHoldersResponse {
+    pub accounts: Vec<HolderBalance>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HolderBalance {
+    pub owner: String,
+    pub account: CurrencyAccount,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HandlerLockedBalance {
+    pub currency_id: String,
+    pub handler: String,
+    pub locked: u128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LockedBalancesResponse {
+    pub locks: Vec<HandlerLockedBalance>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LockResponse {
+    pub lock_id: u64,
+    pub lock: LockRecord,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LocksResponse {
+    pub locks: Vec<LockResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HandlerAllowanceInfo {
+    pub currency_id: String,
+    pub handler: String,
+    pub remaining: u128,
+    pub locked: u128,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HandlerAllowancesResponse {
+    pub allowances: Vec<HandlerAllowanceInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CurrencyResponse {
+    pub currency: Currency,
+    pub status: CurrencyStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CurrenciesResponse {
+    pub currencies: Vec<CurrencyResponse>,
 }
@@ -1,13 +1,77 @@
-use cosmwasm_std::Addr;
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Empty};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
-use crate::structs::CurrencyAccount;
+use crate::structs::{
+    Currency, CurrencyAccount, CurrencyStatus, FeeSchedule, HandlerAllowance, HandlerCapUsage,
+    HandlerRecord, LockRecord, PauseStatus,
+};
 
 // authorizations
+// no admin once renounced
 pub const ADMIN: Item<Addr> = Item::new("admin");
+// proposed admin, until it accepts the role
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
+// can trigger the emergency pause alongside the admin
+pub const GUARDIAN: Item<Addr> = Item::new("guardian");
+pub const PAUSE_STATUS: Item<PauseStatus> = Item::new("pause_status");
+// authorizations before the handler registry, moved to HANDLERS on migration
 pub const AUTHORIZED_HANDLERS: Map<Addr, bool> = Map::new("authorized_handlers");
+// registered handlers, using handler address
+pub const HANDLERS: Map<Addr, HandlerRecord> = Map::new("handlers");
+// value counted against each cap, using handler address and identifier for currency
+pub const HANDLER_CAP_USAGE: Map<(Addr, String), HandlerCapUsage> = Map::new("handler_cap_usage");
+// contract notified when locks of a handler are released, using handler address
+pub const HANDLER_HOOKS: Map<Addr, Addr> = Map::new("handler_hooks");
+// allowances given by account owners to handlers, using owner, identifier for currency and handler address
+pub const HANDLER_ALLOWANCES: Map<(String, String, Addr), HandlerAllowance> =
+    Map::new("handler_allowances");
 
-pub const VALID_CURRENCIES: Item<Vec<String>> = Item::new("valid_currencies");
+// accepted currencies, using identifier for currency (denom or token address)
+pub const VALID_CURRENCIES: Map<String, Currency> = Map::new("valid_currencies");
+// accepted identifiers before currency metadata was stored, moved to VALID_CURRENCIES on migration
+pub const LEGACY_VALID_CURRENCIES: Item<Vec<String>> = Item::new("valid_currencies");
+// currencies without status are active
+pub const CURRENCY_STATUSES: Map<String, CurrencyStatus> = Map::new("currency_statuses");
+
+// fees are credited to the collector balance, none are charged without collector
+pub const FEE_COLLECTOR: Item<Addr> = Item::new("fee_collector");
+// currencies without schedule are free
+pub const FEE_SCHEDULES: Map<String, FeeSchedule> = Map::new("fee_schedules");
+// total fees collected per currency, including those since withdrawn by the collector
+pub const ACCRUED_FEES: Map<String, u128> = Map::new("accrued_fees");
 
 // balance using beneficiary identifier and identifier for currency
+// written through state_writes::update_balance only
 pub const BALANCES: Map<(String, String), CurrencyAccount> = Map::new("balances");
+// accounts with a non-zero balance, using identifier for currency and beneficiary identifier
+pub const CURRENCY_HOLDERS: Map<(String, String), Empty> = Map::new("currency_holders");
+// sum of the balances of all accounts, using identifier for currency
+pub const TOTAL_DEPOSITS: Map<String, CurrencyAccount> = Map::new("total_deposits");
+
+// locked value per handler, using beneficiary identifier, identifier for currency and handler address
+// the sum over all handlers matches the locked value of the account in BALANCES
+pub const HANDLER_LOCKS: Map<(String, String, Addr), u128> = Map::new("handler_locks");
+
+// lock records, identified by an incremental id
+pub const LOCK_COUNTER: Item<u64> = Item::new("lock_counter");
+
+pub struct LockIndexes<'a> {
+    pub owner: MultiIndex<'a, String, LockRecord, u64>,
+    pub handler: MultiIndex<'a, Addr, LockRecord, u64>,
+}
+
+impl<'a> IndexList<LockRecord> for LockIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<LockRecord>> + '_> {
+        let v: Vec<&dyn Index<LockRecord>> = vec![&self.owner, &self.handler];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn locks<'a>() -> IndexedMap<'a, u64, LockRecord, LockIndexes<'a>> {
+    let indexes = LockIndexes {
+        owner: MultiIndex::new(|lock| lock.owner.clone(), "locks", "locks__owner"),
+        handler: MultiIndex::new(|lock| lock.handler.clone(), "locks", "locks__handler"),
+    };
+
+    return IndexedMap::new("locks", indexes);
+}
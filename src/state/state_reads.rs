@@ -1,37 +1,258 @@
-use cosmwasm_std::{Addr, Deps};
+use cosmwasm_std::{Addr, Deps, Order};
 //use cw721::Approval;
 
-use crate::{error::ContractError, structs::CurrencyAccount};
+use cw_storage_plus::Bound;
 
-use crate::state::state_entries::ADMIN;
+use crate::{
+    error::ContractError,
+    structs::{
+        Currency, CurrencyAccount, CurrencyOperation, CurrencyStatus, FeeSchedule,
+        HandlerAllowance, HandlerOperation, HandlerRecord, LockRecord, PauseStatus,
+    },
+};
 
-use super::state_entries::{AUTHORIZED_HANDLERS, BALANCES, VALID_CURRENCIES};
+use crate::state::state_entries::{ADMIN, GUARDIAN, PAUSE_STATUS, PENDING_ADMIN};
+
+use super::state_entries::{
+    locks, ACCRUED_FEES, BALANCES, CURRENCY_HOLDERS, CURRENCY_STATUSES, FEE_COLLECTOR,
+    FEE_SCHEDULES, HANDLERS, HANDLER_ALLOWANCES, HANDLER_HOOKS, HANDLER_LOCKS, TOTAL_DEPOSITS,
+    VALID_CURRENCIES,
+};
 
 pub fn is_admin(deps: Deps, caller: Addr) -> Result<bool, ContractError> {
-    let admin = ADMIN.load(deps.storage)?;
-    return Ok(admin == caller);
+    let admin = ADMIN.may_load(deps.storage)?;
+    return Ok(admin == Some(caller));
+}
+
+pub fn get_admin(deps: Deps) -> Result<Option<Addr>, ContractError> {
+    return Ok(ADMIN.may_load(deps.storage)?);
+}
+
+pub fn is_guardian(deps: Deps, caller: Addr) -> Result<bool, ContractError> {
+    let guardian = GUARDIAN.may_load(deps.storage)?;
+    return Ok(guardian == Some(caller));
+}
+
+pub fn get_guardian(deps: Deps) -> Result<Option<Addr>, ContractError> {
+    return Ok(GUARDIAN.may_load(deps.storage)?);
+}
+
+pub fn get_pause_status(deps: Deps) -> Result<PauseStatus, ContractError> {
+    return Ok(PAUSE_STATUS.may_load(deps.storage)?.unwrap_or_default());
+}
+
+pub fn get_pending_admin(deps: Deps) -> Result<Option<Addr>, ContractError> {
+    return Ok(PENDING_ADMIN.may_load(deps.storage)?);
 }
 
 pub fn is_authorized_handler(deps: Deps, target: Addr) -> Result<bool, ContractError> {
-    let authorization = AUTHORIZED_HANDLERS.load(deps.storage, target);
+    return Ok(HANDLERS.has(deps.storage, target));
+}
+
+pub fn get_authorized_handlers(deps: Deps) -> Result<Vec<Addr>, ContractError> {
+    let handlers = HANDLERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<Result<Vec<Addr>, _>>()?;
+
+    return Ok(handlers);
+}
+
+pub fn get_handler(deps: Deps, handler: Addr) -> Result<HandlerRecord, ContractError> {
+    let record = HANDLERS.may_load(deps.storage, handler.clone())?.ok_or(
+        ContractError::HandlerNotFound {
+            handler: handler.into_string(),
+        },
+    )?;
+
+    return Ok(record);
+}
 
-    match authorization {
-        Err(_) => return Ok(false),
-        Ok(auth) => return Ok(auth),
+pub fn get_handlers(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: usize,
+) -> Result<Vec<(Addr, HandlerRecord)>, ContractError> {
+    let handlers = HANDLERS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<Result<Vec<(Addr, HandlerRecord)>, _>>()?;
+
+    return Ok(handlers);
+}
+
+// unregistered handlers are unauthorized, registered ones are limited by their record
+pub fn check_handler_operation(
+    deps: Deps,
+    handler: Addr,
+    operation: HandlerOperation,
+    currency_identifier: Option<String>,
+) -> Result<HandlerRecord, ContractError> {
+    let record = HANDLERS
+        .may_load(deps.storage, handler)?
+        .ok_or(ContractError::Unauthorized {})?;
+
+    if !record.allows(&operation) {
+        return Err(ContractError::HandlerOperationNotAllowed {
+            operation: operation.to_string(),
+        });
     }
+    if let Some(currency_identifier) = currency_identifier {
+        if !record.allows_currency(&currency_identifier) {
+            return Err(ContractError::HandlerCurrencyNotAllowed {
+                currency_identifier: currency_identifier,
+            });
+        }
+    }
+
+    return Ok(record);
+}
+
+// a revoked handler, or one without an operation releasing locked value, cannot release its locks
+pub fn can_release_locks(
+    deps: Deps,
+    handler: Addr,
+    currency_identifier: String,
+) -> Result<bool, ContractError> {
+    let record = match HANDLERS.may_load(deps.storage, handler)? {
+        Some(record) => record,
+        None => return Ok(false),
+    };
+
+    return Ok(record.allows_currency(&currency_identifier)
+        && (record.allows(&HandlerOperation::Unlock)
+            || record.allows(&HandlerOperation::TransferLocked)));
+}
+
+pub fn get_handler_hook(deps: Deps, handler: Addr) -> Result<Option<Addr>, ContractError> {
+    return Ok(HANDLER_HOOKS.may_load(deps.storage, handler)?);
 }
 
 pub fn is_valid_currency(deps: Deps, currency_identifier: String) -> Result<bool, ContractError> {
-    let valid_currencies = VALID_CURRENCIES.load(deps.storage);
+    return Ok(VALID_CURRENCIES.has(deps.storage, currency_identifier));
+}
 
-    match valid_currencies {
-        Err(_) => {
-            return Err(ContractError::StorageItemNotExist {
-                identifier: currency_identifier,
-            })
-        }
-        Ok(identifiers) => return Ok(identifiers.contains(&currency_identifier)),
+pub fn is_valid_native_currency(deps: Deps, denom: String) -> Result<bool, ContractError> {
+    match VALID_CURRENCIES.may_load(deps.storage, denom)? {
+        Some(Currency::Native { .. }) => return Ok(true),
+        _ => return Ok(false),
+    }
+}
+
+pub fn is_valid_cw20_currency(deps: Deps, token_address: String) -> Result<bool, ContractError> {
+    match VALID_CURRENCIES.may_load(deps.storage, token_address)? {
+        Some(Currency::Cw20 { .. }) => return Ok(true),
+        _ => return Ok(false),
+    }
+}
+
+pub fn get_currency(deps: Deps, currency_identifier: String) -> Result<Currency, ContractError> {
+    let currency = VALID_CURRENCIES
+        .may_load(deps.storage, currency_identifier.clone())?
+        .ok_or(ContractError::CurrencyNotAccepted {
+            currency_identifier: currency_identifier,
+        })?;
+
+    return Ok(currency);
+}
+
+pub fn get_currency_status(
+    deps: Deps,
+    currency_identifier: String,
+) -> Result<CurrencyStatus, ContractError> {
+    let status = CURRENCY_STATUSES
+        .may_load(deps.storage, currency_identifier)?
+        .unwrap_or(CurrencyStatus::Active);
+
+    return Ok(status);
+}
+
+pub fn check_currency_operation(
+    deps: Deps,
+    currency_identifier: String,
+    operation: CurrencyOperation,
+) -> Result<(), ContractError> {
+    if !get_currency_status(deps, currency_identifier.clone())?.allows(&operation) {
+        return Err(ContractError::CurrencyOperationDisabled {
+            currency_identifier: currency_identifier,
+            operation: operation.to_string(),
+        });
+    }
+
+    return Ok(());
+}
+
+pub fn get_fee_collector(deps: Deps) -> Result<Option<Addr>, ContractError> {
+    return Ok(FEE_COLLECTOR.may_load(deps.storage)?);
+}
+
+pub fn get_fee_schedule(
+    deps: Deps,
+    currency_identifier: String,
+) -> Result<FeeSchedule, ContractError> {
+    return Ok(FEE_SCHEDULES
+        .may_load(deps.storage, currency_identifier)?
+        .unwrap_or_default());
+}
+
+pub fn get_accrued_fees(deps: Deps, currency_identifier: String) -> Result<u128, ContractError> {
+    return Ok(ACCRUED_FEES
+        .may_load(deps.storage, currency_identifier)?
+        .unwrap_or_default());
+}
+
+// fee charged on the amount of the operation, nothing while no collector is set
+pub fn compute_fee(
+    deps: Deps,
+    payer: String,
+    currency_identifier: String,
+    operation: CurrencyOperation,
+    amount: u128,
+) -> Result<u128, ContractError> {
+    // the collector pays no fee on the funds it collected
+    match get_fee_collector(deps)? {
+        Some(collector) if collector.as_str() != payer => (),
+        _ => return Ok(0),
     }
+
+    let schedule = get_fee_schedule(deps, currency_identifier.clone())?;
+    let fee = match schedule.fee(&operation) {
+        Some(fee) => fee.compute(amount),
+        None => return Ok(0),
+    };
+
+    if fee > amount {
+        return Err(ContractError::FeeExceedsAmount {
+            currency_identifier: currency_identifier,
+            amount: amount.to_string(),
+            fee: fee.to_string(),
+        });
+    }
+
+    return Ok(fee);
+}
+
+pub fn get_currencies(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: usize,
+) -> Result<Vec<Currency>, ContractError> {
+    let currencies = VALID_CURRENCIES
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|entry| entry.map(|(_, currency)| currency))
+        .collect::<Result<Vec<Currency>, _>>()?;
+
+    return Ok(currencies);
 }
 
 pub fn get_currency_account(
@@ -39,29 +260,226 @@ pub fn get_currency_account(
     owner: String,
     currency_identifier: String,
 ) -> Result<CurrencyAccount, ContractError> {
-    let account = BALANCES.load(deps.storage, (owner, currency_identifier));
+    let account = BALANCES
+        .may_load(deps.storage, (owner, currency_identifier))?
+        .unwrap_or_else(CurrencyAccount::new);
 
-    match account {
-        Ok(acc) => return Ok(acc),
-        Err(_) => return Ok(CurrencyAccount::new()),
-    }
+    return Ok(account);
 }
 
 pub fn get_all_currency_accounts(
     deps: Deps,
     owner: String,
-) -> Result<Vec<CurrencyAccount>, ContractError> {
-    let all_currencies = VALID_CURRENCIES.load(deps.storage)?;
+    start_after: Option<String>,
+    limit: usize,
+    hide_zero: bool,
+) -> Result<Vec<(String, CurrencyAccount)>, ContractError> {
+    let accounts = VALID_CURRENCIES
+        .keys(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .map(|currency_id| -> Result<_, ContractError> {
+            let currency_id = currency_id?;
+            let account = BALANCES
+                .may_load(deps.storage, (owner.clone(), currency_id.clone()))?
+                .unwrap_or_else(CurrencyAccount::new);
+            return Ok((currency_id, account));
+        })
+        .filter(|entry| match entry {
+            Ok((_, account)) => !hide_zero || account.available > 0 || account.locked > 0,
+            Err(_) => true,
+        })
+        .take(limit)
+        .collect::<Result<Vec<(String, CurrencyAccount)>, _>>()?;
 
-    let accounts = all_currencies
-        .into_iter()
-        .map(
-            |currency_id| match BALANCES.load(deps.storage, (owner.clone(), currency_id)) {
-                Ok(val) => val,
-                Err(_) => CurrencyAccount::new(),
-            },
+    return Ok(accounts);
+}
+
+pub fn get_total_deposits(
+    deps: Deps,
+    currency_identifier: String,
+) -> Result<CurrencyAccount, ContractError> {
+    return Ok(TOTAL_DEPOSITS
+        .may_load(deps.storage, currency_identifier)?
+        .unwrap_or_else(CurrencyAccount::new));
+}
+
+pub fn get_all_total_deposits(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: usize,
+) -> Result<Vec<(String, CurrencyAccount)>, ContractError> {
+    let totals = TOTAL_DEPOSITS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
         )
-        .collect();
+        .take(limit)
+        .collect::<Result<Vec<(String, CurrencyAccount)>, _>>()?;
+
+    return Ok(totals);
+}
+
+// amount of the currency owned by the contract according to the bank module or the token contract
+pub fn get_contract_holdings(
+    deps: Deps,
+    contract_address: &Addr,
+    currency: &Currency,
+) -> Result<u128, ContractError> {
+    match currency {
+        Currency::Native { denom, .. } => {
+            let balance = deps.querier.query_balance(contract_address, denom)?;
+            return Ok(balance.amount.u128());
+        }
+        Currency::Cw20 { token_address, .. } => {
+            let balance: cw20::BalanceResponse = deps.querier.query_wasm_smart(
+                token_address,
+                &cw20::Cw20QueryMsg::Balance {
+                    address: contract_address.to_string(),
+                },
+            )?;
+            return Ok(balance.balance.u128());
+        }
+    }
+}
+
+pub fn get_currency_holders(
+    deps: Deps,
+    currency_identifier: String,
+    start_after: Option<String>,
+    limit: usize,
+) -> Result<Vec<(String, CurrencyAccount)>, ContractError> {
+    let holders = CURRENCY_HOLDERS
+        .prefix(currency_identifier.clone())
+        .keys(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<Result<Vec<String>, _>>()?;
+
+    let accounts = holders
+        .into_iter()
+        .map(|owner| -> Result<_, ContractError> {
+            let account =
+                BALANCES.load(deps.storage, (owner.clone(), currency_identifier.clone()))?;
+            return Ok((owner, account));
+        })
+        .collect::<Result<Vec<(String, CurrencyAccount)>, _>>()?;
 
     return Ok(accounts);
 }
+
+pub fn get_handler_locked_values(
+    deps: Deps,
+    owner: String,
+    currency_identifier: String,
+) -> Result<Vec<(Addr, u128)>, ContractError> {
+    let locks = HANDLER_LOCKS
+        .prefix((owner, currency_identifier))
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<Result<Vec<(Addr, u128)>, _>>()?;
+
+    return Ok(locks);
+}
+
+pub fn get_all_handler_locked_values(
+    deps: Deps,
+    owner: String,
+) -> Result<Vec<(String, Addr, u128)>, ContractError> {
+    let locks = HANDLER_LOCKS
+        .sub_prefix(owner)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|entry| {
+            entry.map(|((currency_identifier, handler), locked)| {
+                (currency_identifier, handler, locked)
+            })
+        })
+        .collect::<Result<Vec<(String, Addr, u128)>, _>>()?;
+
+    return Ok(locks);
+}
+
+pub fn get_lock(deps: Deps, lock_id: u64) -> Result<LockRecord, ContractError> {
+    let lock = locks()
+        .may_load(deps.storage, lock_id)?
+        .ok_or(ContractError::LockNotFound { lock_id: lock_id })?;
+
+    return Ok(lock);
+}
+
+pub fn get_locks_by_owner(
+    deps: Deps,
+    owner: String,
+    start_after: Option<u64>,
+    limit: usize,
+) -> Result<Vec<(u64, LockRecord)>, ContractError> {
+    let locks = locks()
+        .idx
+        .owner
+        .prefix(owner)
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<Result<Vec<(u64, LockRecord)>, _>>()?;
+
+    return Ok(locks);
+}
+
+pub fn get_locks_by_handler(
+    deps: Deps,
+    handler: Addr,
+    start_after: Option<u64>,
+    limit: usize,
+) -> Result<Vec<(u64, LockRecord)>, ContractError> {
+    let locks = locks()
+        .idx
+        .handler
+        .prefix(handler)
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<Result<Vec<(u64, LockRecord)>, _>>()?;
+
+    return Ok(locks);
+}
+
+pub fn get_handler_allowances(
+    deps: Deps,
+    owner: String,
+    start_after: Option<(String, Addr)>,
+    limit: usize,
+) -> Result<Vec<(String, Addr, HandlerAllowance)>, ContractError> {
+    let allowances = HANDLER_ALLOWANCES
+        .sub_prefix(owner)
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|entry| {
+            entry.map(|((currency_identifier, handler), allowance)| {
+                (currency_identifier, handler, allowance)
+            })
+        })
+        .collect::<Result<Vec<(String, Addr, HandlerAllowance)>, _>>()?;
+
+    return Ok(allowances);
+}
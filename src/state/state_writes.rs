@@ -1,44 +1,354 @@
-use crate::{error::ContractError, structs::CurrencyAccount};
-use cosmwasm_std::{Addr, Storage};
+use crate::{
+    error::ContractError,
+    structs::{
+        Currency, CurrencyAccount, CurrencyStatus, FeeSchedule, HandlerAllowance, HandlerCapUsage,
+        HandlerRecord, LockRecord,
+    },
+};
+use cosmwasm_std::{Addr, BlockInfo, Empty, Order, Storage};
+use cw_utils::{Duration, Expiration};
 
 use crate::state::state_entries::ADMIN;
 
-use super::state_entries::{BALANCES, VALID_CURRENCIES};
+use super::state_entries::{
+    locks, ACCRUED_FEES, BALANCES, CURRENCY_HOLDERS, CURRENCY_STATUSES, FEE_COLLECTOR,
+    FEE_SCHEDULES, HANDLER_ALLOWANCES, HANDLER_CAP_USAGE, HANDLER_HOOKS, HANDLER_LOCKS,
+    LEGACY_VALID_CURRENCIES, LOCK_COUNTER, TOTAL_DEPOSITS, VALID_CURRENCIES,
+};
 
 pub mod admin {
-    use crate::state::state_entries::AUTHORIZED_HANDLERS;
+    use crate::state::state_entries::{
+        AUTHORIZED_HANDLERS, GUARDIAN, HANDLERS, HANDLER_HOOKS, PAUSE_STATUS, PENDING_ADMIN,
+    };
+    use crate::structs::PauseStatus;
 
     use super::*;
     pub fn update_admin(storage: &mut dyn Storage, new_admin: Addr) -> Result<(), ContractError> {
-        ADMIN.save(storage, &new_admin).unwrap();
+        ADMIN.save(storage, &new_admin)?;
+        PENDING_ADMIN.remove(storage);
 
         return Ok(());
     }
 
+    pub fn remove_admin(storage: &mut dyn Storage) -> Result<(), ContractError> {
+        ADMIN.remove(storage);
+        PENDING_ADMIN.remove(storage);
+
+        return Ok(());
+    }
+
+    pub fn set_pending_admin(
+        storage: &mut dyn Storage,
+        pending_admin: Addr,
+    ) -> Result<(), ContractError> {
+        PENDING_ADMIN.save(storage, &pending_admin)?;
+
+        return Ok(());
+    }
+
+    pub fn set_guardian(
+        storage: &mut dyn Storage,
+        guardian: Option<Addr>,
+    ) -> Result<(), ContractError> {
+        match guardian {
+            Some(guardian) => GUARDIAN.save(storage, &guardian)?,
+            None => GUARDIAN.remove(storage),
+        }
+
+        return Ok(());
+    }
+
+    pub fn set_fee_collector(
+        storage: &mut dyn Storage,
+        collector: Option<Addr>,
+    ) -> Result<(), ContractError> {
+        match collector {
+            Some(collector) => FEE_COLLECTOR.save(storage, &collector)?,
+            None => FEE_COLLECTOR.remove(storage),
+        }
+
+        return Ok(());
+    }
+
+    pub fn set_fee_schedule(
+        storage: &mut dyn Storage,
+        currency_identifier: String,
+        schedule: FeeSchedule,
+    ) -> Result<(), ContractError> {
+        if schedule.transfer_locked.bps > 10000 || schedule.withdraw.bps > 10000 {
+            return Err(ContractError::InvalidFee {});
+        }
+
+        FEE_SCHEDULES.save(storage, currency_identifier, &schedule)?;
+
+        return Ok(());
+    }
+
+    pub fn set_pause_status(
+        storage: &mut dyn Storage,
+        status: PauseStatus,
+    ) -> Result<(), ContractError> {
+        PAUSE_STATUS.save(storage, &status)?;
+
+        return Ok(());
+    }
+
+    pub fn remove_pending_admin(storage: &mut dyn Storage) -> Result<(), ContractError> {
+        if PENDING_ADMIN.may_load(storage)?.is_none() {
+            return Err(ContractError::NoPendingAdmin {});
+        }
+
+        PENDING_ADMIN.remove(storage);
+
+        return Ok(());
+    }
+
+    // authorizing keeps the record of a registered handler, revoking removes it with its hook
     pub fn set_authorization_status(
         storage: &mut dyn Storage,
         target: Addr,
         new_status: bool,
     ) -> Result<(), ContractError> {
-        AUTHORIZED_HANDLERS.save(storage, target, &new_status)?;
+        if !new_status {
+            HANDLERS.remove(storage, target.clone());
+            HANDLER_HOOKS.remove(storage, target.clone());
+            let usages = HANDLER_CAP_USAGE
+                .prefix(target.clone())
+                .keys(storage, None, None, Order::Ascending)
+                .collect::<Result<Vec<String>, _>>()?;
+            for currency_identifier in usages {
+                HANDLER_CAP_USAGE.remove(storage, (target.clone(), currency_identifier));
+            }
+        } else if !HANDLERS.has(storage, target.clone()) {
+            HANDLERS.save(
+                storage,
+                target.clone(),
+                &HandlerRecord::unrestricted(target.into_string()),
+            )?;
+        }
+
+        return Ok(());
+    }
+
+    pub fn register_handler(
+        storage: &mut dyn Storage,
+        handler: Addr,
+        record: HandlerRecord,
+    ) -> Result<(), ContractError> {
+        let currency_identifiers = record
+            .currencies
+            .iter()
+            .flatten()
+            .chain(record.caps.iter().map(|cap| &cap.currency_identifier));
+        for currency_identifier in currency_identifiers {
+            if !VALID_CURRENCIES.has(storage, currency_identifier.clone()) {
+                return Err(ContractError::CurrencyNotAccepted {
+                    currency_identifier: currency_identifier.clone(),
+                });
+            }
+        }
+
+        for (index, cap) in record.caps.iter().enumerate() {
+            let is_duplicate = record.caps[..index]
+                .iter()
+                .any(|other| other.currency_identifier == cap.currency_identifier);
+            let is_empty_period = match cap.period {
+                Duration::Height(blocks) => blocks == 0,
+                Duration::Time(seconds) => seconds == 0,
+            };
+            if cap.amount == 0 || is_empty_period || is_duplicate {
+                return Err(ContractError::InvalidHandlerCap {
+                    currency_identifier: cap.currency_identifier.clone(),
+                });
+            }
+        }
+
+        HANDLERS.save(storage, handler, &record)?;
+
+        return Ok(());
+    }
+
+    // moves authorizations given before the handler registry
+    pub fn migrate_authorized_handlers(storage: &mut dyn Storage) -> Result<(), ContractError> {
+        let authorizations = AUTHORIZED_HANDLERS
+            .range(storage, None, None, Order::Ascending)
+            .collect::<Result<Vec<(Addr, bool)>, _>>()?;
+
+        for (handler, authorized) in authorizations {
+            set_authorization_status(storage, handler.clone(), authorized)?;
+            AUTHORIZED_HANDLERS.remove(storage, handler);
+        }
 
         return Ok(());
     }
 }
 
+// registers the accepted identifiers stored before currency metadata, using the metadata given by the admin
+pub fn migrate_legacy_currencies(
+    storage: &mut dyn Storage,
+    currencies: Vec<Currency>,
+) -> Result<(), ContractError> {
+    let legacy_identifiers = LEGACY_VALID_CURRENCIES
+        .may_load(storage)?
+        .unwrap_or_default();
+
+    for currency in currencies.iter() {
+        if !legacy_identifiers.contains(&currency.identifier()) {
+            return Err(ContractError::CurrencyNotAccepted {
+                currency_identifier: currency.identifier(),
+            });
+        }
+    }
+
+    for currency_identifier in legacy_identifiers {
+        let currency = currencies
+            .iter()
+            .find(|currency| currency.identifier() == currency_identifier)
+            .ok_or(ContractError::MissingCurrencyMetadata {
+                currency_identifier: currency_identifier.clone(),
+            })?;
+        add_valid_currency(storage, currency.clone())?;
+    }
+
+    LEGACY_VALID_CURRENCIES.remove(storage);
+
+    return Ok(());
+}
+
 pub fn add_valid_currency(
+    storage: &mut dyn Storage,
+    currency: Currency,
+) -> Result<(), ContractError> {
+    // a registered identifier cannot change kind, balances would be withdrawn the wrong way
+    if let Some(registered) = VALID_CURRENCIES.may_load(storage, currency.identifier())? {
+        if std::mem::discriminant(&registered) != std::mem::discriminant(&currency) {
+            return Err(ContractError::CurrencyKindMismatch {
+                currency_identifier: currency.identifier(),
+            });
+        }
+    }
+
+    VALID_CURRENCIES.save(storage, currency.identifier(), &currency)?;
+
+    return Ok(());
+}
+
+pub fn remove_valid_currency(
     storage: &mut dyn Storage,
     currency_identifier: String,
 ) -> Result<(), ContractError> {
-    VALID_CURRENCIES.update(storage, |currencies| -> Result<_, ContractError> {
-        let mut currencies: Vec<String> = currencies
-            .into_iter()
-            .filter(|currency| !currency_identifier.eq(currency))
-            .collect();
-        currencies.push(currency_identifier);
+    if !VALID_CURRENCIES.has(storage, currency_identifier.clone()) {
+        return Err(ContractError::CurrencyNotAccepted {
+            currency_identifier: currency_identifier,
+        });
+    }
 
-        return Ok(currencies);
-    })?;
+    // funds must have left the vault before the currency can be removed
+    let totals = TOTAL_DEPOSITS
+        .may_load(storage, currency_identifier.clone())?
+        .unwrap_or_else(CurrencyAccount::new);
+    if totals.available > 0 || totals.locked > 0 {
+        return Err(ContractError::CurrencyStillHeld {
+            currency_identifier: currency_identifier,
+        });
+    }
+
+    VALID_CURRENCIES.remove(storage, currency_identifier.clone());
+    CURRENCY_STATUSES.remove(storage, currency_identifier.clone());
+    TOTAL_DEPOSITS.remove(storage, currency_identifier);
+
+    return Ok(());
+}
+
+pub fn set_currency_status(
+    storage: &mut dyn Storage,
+    currency_identifier: String,
+    status: CurrencyStatus,
+) -> Result<(), ContractError> {
+    if !VALID_CURRENCIES.has(storage, currency_identifier.clone()) {
+        return Err(ContractError::CurrencyNotAccepted {
+            currency_identifier: currency_identifier,
+        });
+    }
+
+    CURRENCY_STATUSES.save(storage, currency_identifier, &status)?;
+
+    return Ok(());
+}
+
+pub fn set_handler_hook(
+    storage: &mut dyn Storage,
+    handler: Addr,
+    hook: Option<Addr>,
+) -> Result<(), ContractError> {
+    match hook {
+        Some(hook) => HANDLER_HOOKS.save(storage, handler, &hook)?,
+        None => HANDLER_HOOKS.remove(storage, handler),
+    }
+
+    return Ok(());
+}
+
+pub fn set_handler_allowance(
+    storage: &mut dyn Storage,
+    owner: String,
+    currency_identifier: String,
+    handler: Addr,
+    allowance: HandlerAllowance,
+) -> Result<(), ContractError> {
+    HANDLER_ALLOWANCES.save(storage, (owner, currency_identifier, handler), &allowance)?;
+
+    return Ok(());
+}
+
+// spends the unexpired allowance of the owner for a lock by the handler, removing it once used up
+pub fn use_handler_allowance(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    owner: String,
+    currency_identifier: String,
+    handler: Addr,
+    amount_to_lock: u128,
+) -> Result<(), ContractError> {
+    let allowance_key = (owner, currency_identifier.clone(), handler);
+    let mut allowance = HANDLER_ALLOWANCES
+        .may_load(storage, allowance_key.clone())?
+        .ok_or(ContractError::NoAllowance {})?;
+
+    if allowance.expires.is_expired(block) {
+        return Err(ContractError::Expired {});
+    }
+
+    if amount_to_lock > allowance.remaining {
+        return Err(ContractError::InsufficientAllowance {
+            currency_identifier: currency_identifier,
+            available: allowance.remaining.to_string(),
+            required: amount_to_lock.to_string(),
+        });
+    }
+
+    allowance.remaining -= amount_to_lock;
+    if allowance.remaining == 0 {
+        HANDLER_ALLOWANCES.remove(storage, allowance_key);
+    } else {
+        HANDLER_ALLOWANCES.save(storage, allowance_key, &allowance)?;
+    }
+
+    return Ok(());
+}
+
+pub fn remove_handler_allowance(
+    storage: &mut dyn Storage,
+    owner: String,
+    currency_identifier: String,
+    handler: Addr,
+) -> Result<(), ContractError> {
+    let allowance_key = (owner, currency_identifier, handler);
+    if !HANDLER_ALLOWANCES.has(storage, allowance_key.clone()) {
+        return Err(ContractError::NoAllowance {});
+    }
+
+    HANDLER_ALLOWANCES.remove(storage, allowance_key);
 
     return Ok(());
 }
@@ -49,7 +359,182 @@ pub fn update_currency_account(
     currency_identifier: String,
     account_data: CurrencyAccount,
 ) -> Result<(), ContractError> {
-    BALANCES.save(storage, (beneficiary, currency_identifier), &account_data)?;
+    update_balance(storage, beneficiary, currency_identifier, |_| {
+        return Ok(account_data);
+    })?;
+
+    return Ok(());
+}
+
+// every write to BALANCES goes through here to keep CURRENCY_HOLDERS and TOTAL_DEPOSITS in sync
+pub fn update_balance<A>(
+    storage: &mut dyn Storage,
+    owner: String,
+    currency_identifier: String,
+    action: A,
+) -> Result<CurrencyAccount, ContractError>
+where
+    A: FnOnce(Option<CurrencyAccount>) -> Result<CurrencyAccount, ContractError>,
+{
+    let previous = BALANCES.may_load(storage, (owner.clone(), currency_identifier.clone()))?;
+    let account = action(previous.clone())?;
+    BALANCES.save(
+        storage,
+        (owner.clone(), currency_identifier.clone()),
+        &account,
+    )?;
+
+    let previous = previous.unwrap_or_else(CurrencyAccount::new);
+    TOTAL_DEPOSITS.update(
+        storage,
+        currency_identifier.clone(),
+        |totals| -> Result<_, ContractError> {
+            let mut totals = totals.unwrap_or_else(CurrencyAccount::new);
+            totals.available = totals.available + account.available - previous.available;
+            totals.locked = totals.locked + account.locked - previous.locked;
+            return Ok(totals);
+        },
+    )?;
+
+    if account.available > 0 || account.locked > 0 {
+        CURRENCY_HOLDERS.save(storage, (currency_identifier, owner), &Empty {})?;
+    } else {
+        CURRENCY_HOLDERS.remove(storage, (currency_identifier, owner));
+    }
+
+    return Ok(account);
+}
+
+// moves locked value not covered by HANDLER_LOCKS back to available, for locks taken before they were tracked
+// writes BALANCES directly, the indexes are recomputed by rebuild_balance_indexes afterwards
+pub fn release_untracked_locks(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let balances = BALANCES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<Result<Vec<((String, String), CurrencyAccount)>, _>>()?;
+
+    for ((owner, currency_identifier), mut account) in balances {
+        if account.locked == 0 {
+            continue;
+        }
+
+        let tracked: u128 = HANDLER_LOCKS
+            .prefix((owner.clone(), currency_identifier.clone()))
+            .range(storage, None, None, Order::Ascending)
+            .map(|entry| entry.map(|(_, locked)| locked))
+            .collect::<Result<Vec<u128>, _>>()?
+            .iter()
+            .sum();
+
+        if account.locked > tracked {
+            account.available += account.locked - tracked;
+            account.locked = tracked;
+            BALANCES.save(storage, (owner, currency_identifier), &account)?;
+        }
+    }
+
+    return Ok(());
+}
+
+// recomputes CURRENCY_HOLDERS and TOTAL_DEPOSITS from BALANCES, for balances written before they existed
+pub fn rebuild_balance_indexes(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let balances = BALANCES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<Result<Vec<((String, String), CurrencyAccount)>, _>>()?;
+
+    let mut totals: Vec<(String, CurrencyAccount)> = vec![];
+    for ((owner, currency_identifier), account) in balances {
+        if account.available == 0 && account.locked == 0 {
+            continue;
+        }
+        CURRENCY_HOLDERS.save(storage, (currency_identifier.clone(), owner), &Empty {})?;
+
+        match totals
+            .iter_mut()
+            .find(|(currency, _)| *currency == currency_identifier)
+        {
+            Some((_, total)) => {
+                total.available += account.available;
+                total.locked += account.locked;
+            }
+            None => totals.push((currency_identifier, account)),
+        }
+    }
+
+    for (currency_identifier, total) in totals {
+        TOTAL_DEPOSITS.save(storage, currency_identifier, &total)?;
+    }
+
+    return Ok(());
+}
+
+// counts value transferred from locks against the cap of the handler for the currency, if any
+pub fn use_handler_cap(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    handler: Addr,
+    record: &HandlerRecord,
+    currency_identifier: String,
+    amount: u128,
+) -> Result<(), ContractError> {
+    let cap = match record.cap(&currency_identifier) {
+        Some(cap) => cap,
+        None => return Ok(()),
+    };
+
+    let key = (handler, currency_identifier.clone());
+    let usage = match HANDLER_CAP_USAGE.may_load(storage, key.clone())? {
+        Some(usage) if !usage.resets.is_expired(block) => usage,
+        _ => HandlerCapUsage {
+            used: 0,
+            resets: cap.period.after(block),
+        },
+    };
+
+    let available = cap.amount.saturating_sub(usage.used);
+    if amount > available {
+        return Err(ContractError::HandlerCapExceeded {
+            currency_identifier: currency_identifier,
+            available: available.to_string(),
+            required: amount.to_string(),
+        });
+    }
+
+    HANDLER_CAP_USAGE.save(
+        storage,
+        key,
+        &HandlerCapUsage {
+            used: usage.used + amount,
+            resets: usage.resets,
+        },
+    )?;
+
+    return Ok(());
+}
+
+// credits a fee, computed while a collector is set, to the collector balance
+pub fn collect_fee(
+    storage: &mut dyn Storage,
+    currency_identifier: String,
+    fee: u128,
+) -> Result<(), ContractError> {
+    if fee == 0 {
+        return Ok(());
+    }
+
+    let collector = FEE_COLLECTOR.load(storage)?;
+    update_deposit(
+        storage,
+        collector.into_string(),
+        currency_identifier.clone(),
+        fee,
+    )?;
+    ACCRUED_FEES.update(
+        storage,
+        currency_identifier,
+        |accrued| -> Result<_, ContractError> {
+            return Ok(accrued.unwrap_or_default() + fee);
+        },
+    )?;
 
     return Ok(());
 }
@@ -60,9 +545,10 @@ pub fn update_deposit(
     currency_identifier: String,
     amount: u128,
 ) -> Result<(), ContractError> {
-    BALANCES.update(
+    update_balance(
         storage,
-        (beneficiary, currency_identifier),
+        beneficiary,
+        currency_identifier,
         |balance| -> Result<_, ContractError> {
             let mut balance = match balance {
                 Some(b) => b,
@@ -78,36 +564,53 @@ pub fn update_deposit(
     return Ok(());
 }
 
-pub fn increase_locked_value(
+pub fn transfer_available_value(
     storage: &mut dyn Storage,
     account_holder: String,
+    recipient: String,
     currency_identifier: String,
-    amount_to_lock: u128,
+    amount_to_transfer: u128,
 ) -> Result<(), ContractError> {
-    BALANCES.update(
+    update_balance(
         storage,
-        (account_holder.clone(), currency_identifier.clone()),
+        account_holder.clone(),
+        currency_identifier.clone(),
         |account| -> Result<_, ContractError> {
             let mut acc = match account {
                 None => {
                     return Err(ContractError::AccountNotFound {
-                        owner: account_holder,
-                        currency_identifier: currency_identifier,
+                        owner: account_holder.clone(),
+                        currency_identifier: currency_identifier.clone(),
                     })
                 }
                 Some(val) => val,
             };
 
-            if amount_to_lock > acc.available {
-                return Err(ContractError::InsufficientFundsAvailableForLock {
-                    currency_identifier: currency_identifier,
+            if amount_to_transfer > acc.available {
+                return Err(ContractError::InsufficientFundsAvailableForTransfer {
+                    currency_identifier: currency_identifier.clone(),
                     available: acc.available.to_string(),
-                    required: amount_to_lock.to_string(),
+                    required: amount_to_transfer.to_string(),
                 });
             }
 
-            acc.available -= amount_to_lock;
-            acc.locked += amount_to_lock;
+            acc.available -= amount_to_transfer;
+
+            return Ok(acc);
+        },
+    )?;
+
+    update_balance(
+        storage,
+        recipient,
+        currency_identifier,
+        |account| -> Result<_, ContractError> {
+            let mut acc = match account {
+                None => CurrencyAccount::new(),
+                Some(val) => val,
+            };
+
+            acc.available += amount_to_transfer;
 
             return Ok(acc);
         },
@@ -116,54 +619,254 @@ pub fn increase_locked_value(
     return Ok(());
 }
 
-pub fn decrease_locked_value(
+pub fn increase_locked_value(
     storage: &mut dyn Storage,
+    block: &BlockInfo,
     account_holder: String,
     currency_identifier: String,
-    amount_to_unlock: u128,
-) -> Result<(), ContractError> {
-    BALANCES.update(
+    handler: Addr,
+    amount_to_lock: u128,
+    expires: Expiration,
+) -> Result<u64, ContractError> {
+    if expires.is_expired(block) {
+        return Err(ContractError::Expired {});
+    }
+
+    update_balance(
         storage,
-        (account_holder.clone(), currency_identifier.clone()),
+        account_holder.clone(),
+        currency_identifier.clone(),
         |account| -> Result<_, ContractError> {
             let mut acc = match account {
                 None => {
                     return Err(ContractError::AccountNotFound {
-                        owner: account_holder,
-                        currency_identifier: currency_identifier,
+                        owner: account_holder.clone(),
+                        currency_identifier: currency_identifier.clone(),
                     })
                 }
                 Some(val) => val,
             };
 
-            if amount_to_unlock > acc.locked {
-                return Err(ContractError::InsufficientFundsLockedForUnlock {
-                    currency_identifier: currency_identifier,
-                    available: acc.locked.to_string(),
-                    required: amount_to_unlock.to_string(),
+            if amount_to_lock > acc.available {
+                return Err(ContractError::InsufficientFundsAvailableForLock {
+                    currency_identifier: currency_identifier.clone(),
+                    available: acc.available.to_string(),
+                    required: amount_to_lock.to_string(),
                 });
             }
 
-            acc.locked -= amount_to_unlock;
-            acc.available += amount_to_unlock;
+            acc.available -= amount_to_lock;
+            acc.locked += amount_to_lock;
 
             return Ok(acc);
         },
     )?;
 
+    let lock_key = (
+        account_holder.clone(),
+        currency_identifier.clone(),
+        handler.clone(),
+    );
+    let locked = HANDLER_LOCKS
+        .may_load(storage, lock_key.clone())?
+        .unwrap_or_default();
+    update_handler_lock(storage, lock_key, locked + amount_to_lock)?;
+
+    let lock_id = LOCK_COUNTER.may_load(storage)?.unwrap_or_default() + 1;
+    LOCK_COUNTER.save(storage, &lock_id)?;
+
+    locks().save(
+        storage,
+        lock_id,
+        &LockRecord {
+            owner: account_holder,
+            currency_identifier: currency_identifier,
+            handler: handler,
+            amount: amount_to_lock,
+            initial_amount: amount_to_lock,
+            created_height: block.height,
+            created_time: block.time,
+            expires: expires,
+        },
+    )?;
+
+    return Ok(lock_id);
+}
+
+pub fn decrease_locked_value(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    account_holder: String,
+    currency_identifier: String,
+    handler: Addr,
+    lock_id: Option<u64>,
+    amount_to_unlock: u128,
+) -> Result<(), ContractError> {
+    let locked = releasable_locked_value(
+        storage,
+        block,
+        &account_holder,
+        &currency_identifier,
+        &handler,
+        lock_id,
+    )?;
+
+    if amount_to_unlock > locked {
+        return Err(ContractError::InsufficientFundsLockedForUnlock {
+            currency_identifier: currency_identifier,
+            available: locked.to_string(),
+            required: amount_to_unlock.to_string(),
+        });
+    }
+
+    consume_locks(
+        storage,
+        block,
+        &account_holder,
+        &currency_identifier,
+        &handler,
+        lock_id,
+        amount_to_unlock,
+    )?;
+
+    release_locked_value(
+        storage,
+        account_holder,
+        currency_identifier,
+        amount_to_unlock,
+    )?;
+
     return Ok(());
 }
 
+// owner takes back the remaining value of an expired lock, or of any lock once its handler cannot release it
+pub fn reclaim_expired_lock(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    account_holder: String,
+    lock_id: u64,
+    handler_can_release: bool,
+) -> Result<LockRecord, ContractError> {
+    let lock = locks()
+        .may_load(storage, lock_id)?
+        .ok_or(ContractError::LockNotFound { lock_id: lock_id })?;
+
+    if lock.owner != account_holder {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if handler_can_release && !lock.expires.is_expired(block) {
+        return Err(ContractError::LockNotExpired { lock_id: lock_id });
+    }
+
+    locks().remove(storage, lock_id)?;
+
+    let lock_key = (
+        lock.owner.clone(),
+        lock.currency_identifier.clone(),
+        lock.handler.clone(),
+    );
+    let locked = HANDLER_LOCKS
+        .may_load(storage, lock_key.clone())?
+        .unwrap_or_default();
+    update_handler_lock(storage, lock_key, locked - lock.amount)?;
+
+    release_locked_value(
+        storage,
+        lock.owner.clone(),
+        lock.currency_identifier.clone(),
+        lock.amount,
+    )?;
+
+    return Ok(lock);
+}
+
+// credits must add up to the transferred amount minus the fee
 pub fn transfer_locked_value(
     storage: &mut dyn Storage,
+    block: &BlockInfo,
     account_holder: String,
-    beneficiary: String,
     currency_identifier: String,
+    handler: Addr,
+    lock_id: Option<u64>,
     amount_to_transfer: u128,
+    credits: Vec<(String, u128)>,
+    fee: u128,
 ) -> Result<(), ContractError> {
-    BALANCES.update(
+    debit_locked_value(
         storage,
-        (account_holder.clone(), currency_identifier.clone()),
+        block,
+        account_holder,
+        currency_identifier.clone(),
+        handler,
+        lock_id,
+        amount_to_transfer,
+    )?;
+
+    for (recipient, amount) in credits {
+        update_balance(
+            storage,
+            recipient,
+            currency_identifier.clone(),
+            |account| -> Result<_, ContractError> {
+                let mut acc = match account {
+                    None => CurrencyAccount::new(),
+                    Some(val) => val,
+                };
+
+                acc.available += amount;
+
+                return Ok(acc);
+            },
+        )?;
+    }
+
+    collect_fee(storage, currency_identifier, fee)?;
+
+    return Ok(());
+}
+
+// takes locked value out of the account, the caller pays it to its recipient
+pub fn debit_locked_value(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    account_holder: String,
+    currency_identifier: String,
+    handler: Addr,
+    lock_id: Option<u64>,
+    amount_to_transfer: u128,
+) -> Result<(), ContractError> {
+    let locked = releasable_locked_value(
+        storage,
+        block,
+        &account_holder,
+        &currency_identifier,
+        &handler,
+        lock_id,
+    )?;
+
+    if amount_to_transfer > locked {
+        return Err(ContractError::InsufficientFundsLockedForTransfer {
+            currency_identifier: currency_identifier,
+            available: locked.to_string(),
+            required: amount_to_transfer.to_string(),
+        });
+    }
+
+    consume_locks(
+        storage,
+        block,
+        &account_holder,
+        &currency_identifier,
+        &handler,
+        lock_id,
+        amount_to_transfer,
+    )?;
+
+    update_balance(
+        storage,
+        account_holder.clone(),
+        currency_identifier.clone(),
         |account| -> Result<_, ContractError> {
             let mut acc = match account {
                 None => {
@@ -175,30 +878,38 @@ pub fn transfer_locked_value(
                 Some(val) => val,
             };
 
-            if amount_to_transfer > acc.locked {
-                return Err(ContractError::InsufficientFundsLockedForTransfer {
-                    currency_identifier: currency_identifier.clone(),
-                    available: acc.locked.to_string(),
-                    required: amount_to_transfer.to_string(),
-                });
-            }
-
             acc.locked -= amount_to_transfer;
 
             return Ok(acc);
         },
     )?;
 
-    BALANCES.update(
+    return Ok(());
+}
+
+fn release_locked_value(
+    storage: &mut dyn Storage,
+    account_holder: String,
+    currency_identifier: String,
+    amount_to_unlock: u128,
+) -> Result<(), ContractError> {
+    update_balance(
         storage,
-        (beneficiary.clone(), currency_identifier.clone()),
+        account_holder.clone(),
+        currency_identifier.clone(),
         |account| -> Result<_, ContractError> {
             let mut acc = match account {
-                None => CurrencyAccount::new(),
+                None => {
+                    return Err(ContractError::AccountNotFound {
+                        owner: account_holder,
+                        currency_identifier: currency_identifier,
+                    })
+                }
                 Some(val) => val,
             };
 
-            acc.available += amount_to_transfer;
+            acc.locked -= amount_to_unlock;
+            acc.available += amount_to_unlock;
 
             return Ok(acc);
         },
@@ -206,3 +917,153 @@ pub fn transfer_locked_value(
 
     return Ok(());
 }
+
+// locks of the handler on the account that have not expired yet, from the oldest one
+fn active_handler_locks(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    account_holder: &str,
+    currency_identifier: &str,
+    handler: &Addr,
+) -> Result<Vec<(u64, LockRecord)>, ContractError> {
+    let active_locks = locks()
+        .idx
+        .owner
+        .prefix(account_holder.to_string())
+        .range(storage, None, None, Order::Ascending)
+        .filter(|entry| match entry {
+            Ok((_, lock)) => {
+                lock.currency_identifier == currency_identifier
+                    && lock.handler == *handler
+                    && !lock.expires.is_expired(block)
+            }
+            Err(_) => true,
+        })
+        .collect::<Result<_, _>>()?;
+
+    return Ok(active_locks);
+}
+
+// the targeted lock, checked against the handler and the account it is used on
+fn handler_lock(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    account_holder: &str,
+    currency_identifier: &str,
+    handler: &Addr,
+    lock_id: u64,
+) -> Result<LockRecord, ContractError> {
+    let lock = locks()
+        .may_load(storage, lock_id)?
+        .ok_or(ContractError::LockNotFound { lock_id: lock_id })?;
+
+    if lock.handler != *handler {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if lock.owner != account_holder || lock.currency_identifier != currency_identifier {
+        return Err(ContractError::LockMismatch {
+            lock_id: lock_id,
+            owner: account_holder.to_string(),
+            currency_identifier: currency_identifier.to_string(),
+        });
+    }
+
+    if lock.expires.is_expired(block) {
+        return Err(ContractError::Expired {});
+    }
+
+    return Ok(lock);
+}
+
+// value the handler can release, either from a single lock or from all its active locks on the account
+fn releasable_locked_value(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    account_holder: &str,
+    currency_identifier: &str,
+    handler: &Addr,
+    lock_id: Option<u64>,
+) -> Result<u128, ContractError> {
+    match lock_id {
+        Some(lock_id) => {
+            let lock = handler_lock(
+                storage,
+                block,
+                account_holder,
+                currency_identifier,
+                handler,
+                lock_id,
+            )?;
+
+            return Ok(lock.amount);
+        }
+        None => {
+            let active_locks =
+                active_handler_locks(storage, block, account_holder, currency_identifier, handler)?;
+
+            return Ok(active_locks.iter().map(|(_, lock)| lock.amount).sum());
+        }
+    }
+}
+
+// consume the targeted lock, or the active handler locks on the account from the oldest one
+// the amount must have been checked against releasable_locked_value beforehand
+fn consume_locks(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    account_holder: &str,
+    currency_identifier: &str,
+    handler: &Addr,
+    lock_id: Option<u64>,
+    amount: u128,
+) -> Result<(), ContractError> {
+    let targets: Vec<(u64, LockRecord)> = match lock_id {
+        Some(lock_id) => vec![(lock_id, locks().load(storage, lock_id)?)],
+        None => active_handler_locks(storage, block, account_holder, currency_identifier, handler)?,
+    };
+
+    let mut remaining = amount;
+    for (lock_id, mut lock) in targets {
+        if remaining == 0 {
+            break;
+        }
+
+        let consumed = remaining.min(lock.amount);
+        lock.amount -= consumed;
+        remaining -= consumed;
+
+        if lock.amount == 0 {
+            locks().remove(storage, lock_id)?;
+        } else {
+            locks().save(storage, lock_id, &lock)?;
+        }
+    }
+
+    let lock_key = (
+        account_holder.to_string(),
+        currency_identifier.to_string(),
+        handler.clone(),
+    );
+    let locked = HANDLER_LOCKS
+        .may_load(storage, lock_key.clone())?
+        .unwrap_or_default();
+    update_handler_lock(storage, lock_key, locked - amount)?;
+
+    return Ok(());
+}
+
+// zero locks are removed so that only active locks are listed per handler
+fn update_handler_lock(
+    storage: &mut dyn Storage,
+    lock_key: (String, String, Addr),
+    locked: u128,
+) -> Result<(), ContractError> {
+    if locked == 0 {
+        HANDLER_LOCKS.remove(storage, lock_key);
+    } else {
+        HANDLER_LOCKS.save(storage, lock_key, &locked)?;
+    }
+
+    return Ok(());
+}
@@ -1,3 +1,7 @@
+use std::fmt;
+
+use cosmwasm_std::{Addr, Api, StdResult, Timestamp, Uint128};
+use cw_utils::{Duration, Expiration};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -16,6 +20,139 @@ impl CurrencyAccount {
     }
 }
 
+// operations stopped by the emergency pause
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PauseStatus {
+    pub deposits: bool,
+    pub withdrawals: bool,
+    // transfers of available funds between depositors
+    pub transfers: bool,
+    // lock, unlock and reclaim of expired locks
+    pub locks: bool,
+    pub locked_transfers: bool,
+}
+
+impl PauseStatus {
+    pub fn all() -> Self {
+        return PauseStatus {
+            deposits: true,
+            withdrawals: true,
+            transfers: true,
+            locks: true,
+            locked_transfers: true,
+        };
+    }
+}
+
+// funds locked by a handler on an account, consumed by unlocks and transfers
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockRecord {
+    pub owner: String,
+    pub currency_identifier: String,
+    pub handler: Addr,
+    pub amount: u128,
+    pub initial_amount: u128,
+    pub created_height: u64,
+    pub created_time: Timestamp,
+    // once expired, the handler can no longer use the lock and the owner can reclaim it
+    pub expires: Expiration,
+}
+
+// consent given by an account owner for a handler to lock its funds
+// each lock spends from remaining, the allowance is removed once it is used up
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HandlerAllowance {
+    #[serde(alias = "max_amount")]
+    pub remaining: u128,
+    pub expires: Expiration,
+}
+
+// operations a handler can perform on funds locked for it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandlerOperation {
+    Lock,
+    Unlock,
+    TransferLocked,
+    Settle,
+}
+
+impl fmt::Display for HandlerOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operation = match self {
+            HandlerOperation::Lock => "lock",
+            HandlerOperation::Unlock => "unlock",
+            HandlerOperation::TransferLocked => "transfer_locked",
+            HandlerOperation::Settle => "settle",
+        };
+
+        return write!(f, "{}", operation);
+    }
+}
+
+// most a handler can transfer from locks of a currency within each period
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HandlerCap {
+    pub currency_identifier: String,
+    pub amount: u128,
+    pub period: Duration,
+}
+
+// registration of a handler and limits on what it can do with funds locked for it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HandlerRecord {
+    pub name: String,
+    pub description: Option<String>,
+    pub operations: Vec<HandlerOperation>,
+    // None allows every accepted currency
+    pub currencies: Option<Vec<String>>,
+    // currencies without cap are unlimited
+    pub caps: Vec<HandlerCap>,
+}
+
+impl HandlerRecord {
+    // given by SetAuthorizationStatus and at instantiation
+    pub fn unrestricted(name: String) -> Self {
+        return HandlerRecord {
+            name: name,
+            description: None,
+            operations: vec![
+                HandlerOperation::Lock,
+                HandlerOperation::Unlock,
+                HandlerOperation::TransferLocked,
+                HandlerOperation::Settle,
+            ],
+            currencies: None,
+            caps: vec![],
+        };
+    }
+
+    pub fn allows(&self, operation: &HandlerOperation) -> bool {
+        return self.operations.contains(operation);
+    }
+
+    pub fn allows_currency(&self, currency_identifier: &str) -> bool {
+        match &self.currencies {
+            Some(currencies) => return currencies.iter().any(|id| id == currency_identifier),
+            None => return true,
+        }
+    }
+
+    pub fn cap(&self, currency_identifier: &str) -> Option<&HandlerCap> {
+        return self
+            .caps
+            .iter()
+            .find(|cap| cap.currency_identifier == currency_identifier);
+    }
+}
+
+// value counted against a handler cap until the period ends
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HandlerCapUsage {
+    pub used: u128,
+    pub resets: Expiration,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Currency {
@@ -30,3 +167,152 @@ pub enum Currency {
         token_address: String,
     }, //Cw20(Cw20Currency),
 }
+
+impl Currency {
+    // denom for native currencies, token address for cw20
+    pub fn identifier(&self) -> String {
+        match self {
+            Currency::Native { denom, .. } => return denom.clone(),
+            Currency::Cw20 { token_address, .. } => return token_address.clone(),
+        }
+    }
+
+    // normalizes the token address of cw20 currencies
+    pub fn validate(self, api: &dyn Api) -> StdResult<Currency> {
+        match self {
+            Currency::Cw20 {
+                name,
+                ticker,
+                token_address,
+            } => {
+                return Ok(Currency::Cw20 {
+                    name: name,
+                    ticker: ticker,
+                    token_address: api.addr_validate(&token_address)?.into_string(),
+                })
+            }
+            native => return Ok(native),
+        }
+    }
+
+    pub fn asset_info(&self) -> AssetInfo {
+        match self {
+            Currency::Native { denom, .. } => {
+                return AssetInfo::Native {
+                    denom: denom.clone(),
+                }
+            }
+            Currency::Cw20 { token_address, .. } => {
+                return AssetInfo::Cw20 {
+                    token_address: token_address.clone(),
+                }
+            }
+        }
+    }
+}
+
+// how a currency moves in and out of the vault, without its metadata
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Native { denom: String },
+    Cw20 { token_address: String },
+}
+
+impl AssetInfo {
+    // same identifier as the matching currency
+    pub fn identifier(&self) -> String {
+        match self {
+            AssetInfo::Native { denom } => return denom.clone(),
+            AssetInfo::Cw20 { token_address } => return token_address.clone(),
+        }
+    }
+}
+
+// charged on an amount: bps basis points of it plus a flat part
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Fee {
+    pub bps: u64,
+    pub flat: u128,
+}
+
+impl Fee {
+    pub fn compute(&self, amount: u128) -> u128 {
+        return Uint128::from(amount)
+            .multiply_ratio(self.bps, 10000u64)
+            .u128()
+            .saturating_add(self.flat);
+    }
+}
+
+// fees of a currency, credited to the fee collector account inside the vault
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct FeeSchedule {
+    pub transfer_locked: Fee,
+    pub withdraw: Fee,
+}
+
+impl FeeSchedule {
+    pub fn fee(&self, operation: &CurrencyOperation) -> Option<&Fee> {
+        match operation {
+            CurrencyOperation::TransferLocked => return Some(&self.transfer_locked),
+            CurrencyOperation::Withdraw => return Some(&self.withdraw),
+            _ => return None,
+        }
+    }
+}
+
+// lifecycle of an accepted currency, used to wind down a delisted one while letting users exit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CurrencyStatus {
+    Active,
+    DepositsPaused,
+    // withdrawals, unlocks and settlement of existing locks only
+    WithdrawOnly,
+    Frozen,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CurrencyOperation {
+    Deposit,
+    Withdraw,
+    Transfer,
+    Lock,
+    Unlock,
+    TransferLocked,
+}
+
+impl CurrencyStatus {
+    pub fn allows(&self, operation: &CurrencyOperation) -> bool {
+        match self {
+            CurrencyStatus::Active => return true,
+            CurrencyStatus::DepositsPaused => return *operation != CurrencyOperation::Deposit,
+            CurrencyStatus::WithdrawOnly => {
+                return matches!(
+                    operation,
+                    CurrencyOperation::Withdraw
+                        | CurrencyOperation::Unlock
+                        | CurrencyOperation::TransferLocked
+                )
+            }
+            CurrencyStatus::Frozen => return false,
+        }
+    }
+}
+
+impl fmt::Display for CurrencyOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operation = match self {
+            CurrencyOperation::Deposit => "deposit",
+            CurrencyOperation::Withdraw => "withdraw",
+            CurrencyOperation::Transfer => "transfer",
+            CurrencyOperation::Lock => "lock",
+            CurrencyOperation::Unlock => "unlock",
+            CurrencyOperation::TransferLocked => "transfer_locked",
+        };
+
+        return write!(f, "{}", operation);
+    }
+}